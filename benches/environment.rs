@@ -0,0 +1,137 @@
+//! Measures variable accesses in the tree-walking interpreter, run with `cargo bench`
+use std::{
+    fmt::Write,
+    time::{Duration, Instant},
+};
+
+use rox::{
+    ast::{Arena, Statement},
+    interpreter::{resolver::Resolver, Interpreter},
+    parser::Parser,
+    scanner::Scanner,
+};
+
+const ITERATIONS: u32 = 20;
+/// Executions of the program per iteration
+const REPEAT: usize = 100;
+
+fn parse<'a>(source: &str, arena: &'a Arena<'a>) -> Vec<Statement<'a>> {
+    let tokens = Scanner::new(source).scan_tokens().unwrap();
+    Parser::new(tokens, arena).parse().unwrap()
+}
+
+/// Time `REPEAT` executions of a program by a fresh interpreter, including the resolution of its
+/// variables, report the fastest and mean runs
+fn bench(name: &str, source: &str) {
+    let arena = Arena::new();
+    let statements = parse(source, &arena);
+    time(name, |interpreter| {
+        interpreter.interpret(&statements).unwrap()
+    });
+}
+
+/// Like [`bench`], with the variables resolved once ahead of the executions, as a function or loop
+/// body run many times would be
+fn bench_resolved(name: &str, source: &str) {
+    let arena = Arena::new();
+    let statements = parse(source, &arena);
+    let resolutions = Resolver::resolve(&statements);
+    time(name, |interpreter| {
+        interpreter
+            .interpret_resolved(&statements, resolutions.clone())
+            .unwrap()
+    });
+}
+
+fn time(name: &str, mut run: impl FnMut(&mut Interpreter)) {
+    let mut fastest = Duration::MAX;
+    let mut total = Duration::ZERO;
+    for _ in 0..ITERATIONS {
+        let mut interpreter = Interpreter::default();
+        let start = Instant::now();
+        for _ in 0..REPEAT {
+            run(&mut interpreter);
+        }
+        let elapsed = start.elapsed();
+        fastest = fastest.min(elapsed);
+        total += elapsed;
+    }
+    println!(
+        "{name:<24} fastest {fastest:>12.3?}   mean {:>12.3?}",
+        total / ITERATIONS
+    );
+}
+
+/// `depth` nested blocks each declaring a variable, the innermost one reading the variables of
+/// the outermost and innermost blocks `reads` times
+fn nested_blocks(depth: usize, reads: usize) -> String {
+    let mut source = String::new();
+    for d in 0..depth {
+        writeln!(source, "{{ var v{d} = {d};").unwrap();
+    }
+    for _ in 0..reads {
+        writeln!(source, "var r = v0 + v{};", depth - 1).unwrap();
+    }
+    source.push_str(&"}".repeat(depth));
+    source
+}
+
+/// `depth` nested blocks each declaring a variable, the innermost one reading `reads` times
+/// variables spread over every block, as the body of deeply nested loops would
+fn deep_lookups(depth: usize, reads: usize) -> String {
+    let mut source = String::new();
+    for d in 0..depth {
+        writeln!(source, "{{ var v{d} = {d};").unwrap();
+    }
+    for r in 0..reads {
+        let (a, b, c) = (r % depth, r * 7 % depth, r * 13 % depth);
+        writeln!(source, "var r = v{a} + v{b} * v{c};").unwrap();
+    }
+    source.push_str(&"}".repeat(depth));
+    source
+}
+
+/// A block declaring `variables` variables, then `updates` statements reading and assigning them
+/// as an unrolled loop body would
+fn variable_heavy(variables: usize, updates: usize) -> String {
+    let mut source = String::from("{\n");
+    for v in 0..variables {
+        writeln!(source, "var v{v} = {v};").unwrap();
+    }
+    for u in 0..updates {
+        let (a, b, c) = (u % variables, (u + 1) % variables, (u + 2) % variables);
+        writeln!(source, "v{a} = v{a} + v{b} * v{c} - v{b};").unwrap();
+    }
+    source.push('}');
+    source
+}
+
+/// A block declaring `variables` long strings, then `updates` statements copying and comparing them
+fn string_heavy(variables: usize, updates: usize) -> String {
+    let mut source = String::from("{\n");
+    for v in 0..variables {
+        writeln!(
+            source,
+            "var v{v} = \"{}\";",
+            format!("{v} lorem ipsum ").repeat(20)
+        )
+        .unwrap();
+    }
+    for u in 0..updates {
+        let (a, b, c) = (u % variables, (u + 1) % variables, (u + 2) % variables);
+        writeln!(source, "v{a} = v{b}; var e = v{a} == v{c};").unwrap();
+    }
+    source.push('}');
+    source
+}
+
+fn main() {
+    bench("nested blocks 10", &nested_blocks(10, 500));
+    bench("nested blocks 100", &nested_blocks(100, 500));
+    bench("variable heavy", &variable_heavy(20, 500));
+    bench("strings", &string_heavy(20, 500));
+    bench("globals", &variable_heavy(20, 500)[1..].replace('}', ""));
+    bench("deep lookups 50", &deep_lookups(50, 500));
+    bench_resolved("variable heavy resolved", &variable_heavy(20, 500));
+    bench_resolved("deep lookups 50 resolved", &deep_lookups(50, 500));
+}
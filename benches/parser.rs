@@ -0,0 +1,97 @@
+//! Measures the time and memory taken to scan and parse a program, run with `cargo bench`
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    fmt::Write,
+    sync::atomic::{AtomicUsize, Ordering},
+    time::{Duration, Instant},
+};
+
+use rox::{ast::Arena, parser::Parser, scanner::Scanner};
+
+const ITERATIONS: u32 = 20;
+
+/// Allocator keeping count of the allocations and of the bytes allocated
+struct Counting;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+static ALLOCATED: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        ALLOCATED.fetch_add(layout.size(), Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        ALLOCATED.fetch_sub(layout.size(), Ordering::Relaxed);
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: Counting = Counting;
+
+/// Time the scanning and the parsing of a program, report the fastest runs, then the allocations
+/// made and the memory held by the tokens and by the AST
+fn bench(name: &str, source: &str) {
+    let mut scan = Duration::MAX;
+    let mut parse = Duration::MAX;
+    for _ in 0..ITERATIONS {
+        let start = Instant::now();
+        let tokens = Scanner::new(source).scan_tokens().unwrap();
+        scan = scan.min(start.elapsed());
+
+        let start = Instant::now();
+        let arena = Arena::new();
+        let statements = Parser::new(tokens, &arena).parse().unwrap();
+        parse = parse.min(start.elapsed());
+        drop(statements);
+    }
+
+    let (allocations, before) = (ALLOCATIONS.load(Ordering::Relaxed), allocated());
+    let tokens = Scanner::new(source).scan_tokens().unwrap();
+    let tokens_memory = allocated() - before;
+    let arena = Arena::new();
+    let statements = Parser::new(tokens, &arena).parse().unwrap();
+    let ast_memory = allocated() - before;
+    let allocations = ALLOCATIONS.load(Ordering::Relaxed) - allocations;
+    drop(statements);
+
+    println!(
+        "{name:<16} scan {scan:>10.3?}   parse {parse:>10.3?}   {allocations:>7} allocations   \
+         tokens {:>6} KiB   ast {:>6} KiB",
+        tokens_memory / 1024,
+        ast_memory / 1024
+    );
+}
+
+fn allocated() -> usize {
+    ALLOCATED.load(Ordering::Relaxed)
+}
+
+/// `statements` statements made of literals, as found in data tables
+fn literal_heavy(statements: usize) -> String {
+    let mut source = String::new();
+    for s in 0..statements {
+        writeln!(source, "print \"row {s}\" + \"{}\" == nil != true;", s * 7).unwrap();
+        writeln!(source, "print {s}.5 * 2 - -{s} / 3 >= 1;").unwrap();
+    }
+    source
+}
+
+/// `statements` statements made of identifiers, as found in code
+fn identifier_heavy(statements: usize) -> String {
+    let mut source = String::from("{\n");
+    for s in 0..statements {
+        let (a, b) = (s % 20, (s + 1) % 20);
+        writeln!(source, "var v{a} = v{b} + v{a} * (v{b} - v{a});").unwrap();
+    }
+    source.push('}');
+    source
+}
+
+fn main() {
+    bench("literals", &literal_heavy(1_000));
+    bench("identifiers", &identifier_heavy(2_000));
+}
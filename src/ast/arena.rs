@@ -0,0 +1,30 @@
+use super::Expr;
+
+/// Storage of the expressions of programs, which refer to their children by reference into it
+/// rather than owning them each in its own allocation.
+///
+/// Expressions are allocated in chunks and all freed along with the arena, rewriting a program
+/// (e.g. folding it or editing it incrementally) allocates the new expressions next to the old
+/// ones.
+pub struct Arena<'a> {
+    exprs: typed_arena::Arena<Expr<'a>>,
+}
+
+impl<'a> Arena<'a> {
+    pub fn new() -> Self {
+        Self {
+            exprs: typed_arena::Arena::new(),
+        }
+    }
+
+    /// Move an expression into the arena
+    pub fn alloc(&'a self, expr: Expr<'a>) -> &'a Expr<'a> {
+        self.exprs.alloc(expr)
+    }
+}
+
+impl Default for Arena<'_> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
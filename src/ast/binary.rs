@@ -0,0 +1,578 @@
+use std::{collections::HashMap, fmt::Display, rc::Rc};
+
+use thiserror::Error;
+
+use super::{
+    expression::{Assign, Binary, ErrorExpr, Grouping, Literal, LiteralValue, Unary, Variable},
+    statement::{
+        BlockStatement,
+        ErrorStatement,
+        ExpressionStatement,
+        PrintStatement,
+        VariableStatement,
+    },
+    visitor::{ExprVisitor, StatementVisitor},
+    Arena,
+    Expr,
+    NodeId,
+    Statement,
+};
+use crate::{
+    interner,
+    token::{Span, Token, TokenType},
+};
+
+/// Token types by their index in the encoding, which is their discriminant
+const TOKEN_TYPES: [TokenType; 39] = [
+    TokenType::LeftParen,
+    TokenType::RightParen,
+    TokenType::LeftBrace,
+    TokenType::RightBrace,
+    TokenType::Comma,
+    TokenType::Dot,
+    TokenType::Minus,
+    TokenType::Plus,
+    TokenType::Semicolon,
+    TokenType::Slash,
+    TokenType::Star,
+    TokenType::Bang,
+    TokenType::BangEqual,
+    TokenType::Equal,
+    TokenType::EqualEqual,
+    TokenType::Greater,
+    TokenType::GreaterEqual,
+    TokenType::Less,
+    TokenType::LessEqual,
+    TokenType::Identifier,
+    TokenType::String,
+    TokenType::Number,
+    TokenType::And,
+    TokenType::Class,
+    TokenType::Else,
+    TokenType::False,
+    TokenType::Fun,
+    TokenType::For,
+    TokenType::If,
+    TokenType::Nil,
+    TokenType::Or,
+    TokenType::Print,
+    TokenType::Return,
+    TokenType::Super,
+    TokenType::This,
+    TokenType::True,
+    TokenType::Var,
+    TokenType::While,
+    TokenType::Eof,
+];
+
+#[derive(Error, Debug, PartialEq)]
+pub struct BinaryError {
+    offset: usize,
+    msg: String,
+}
+
+impl BinaryError {
+    pub fn new(offset: usize, msg: String) -> Self {
+        Self { offset, msg }
+    }
+}
+
+impl Display for BinaryError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Binary Error - byte {}: {}", self.offset, self.msg)?;
+
+        Ok(())
+    }
+}
+
+pub type BinaryResult<T> = Result<T, BinaryError>;
+
+/// Encoder of programs into a compact binary representation, read back by [`BinaryReader`].
+///
+/// Integers are LEB128 varints and every node is a tag byte followed by its fields in declaration
+/// order. Strings are written the first time they're met only, later occurrences refer to them by
+/// index, so the lexemes of a variable used many times are stored once.
+#[derive(Default)]
+pub struct BinaryWriter {
+    bytes: Vec<u8>,
+    strings: HashMap<Rc<str>, usize>,
+}
+
+impl BinaryWriter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.bytes
+    }
+
+    pub fn write_u8(&mut self, v: u8) {
+        self.bytes.push(v);
+    }
+
+    pub fn write_usize(&mut self, mut v: usize) {
+        while v >= 0x80 {
+            self.bytes.push(v as u8 | 0x80);
+            v >>= 7;
+        }
+        self.bytes.push(v as u8);
+    }
+
+    pub fn write_f64(&mut self, v: f64) {
+        self.bytes.extend_from_slice(&v.to_le_bytes());
+    }
+
+    /// Write a string as the index of its previous occurrence plus one, or as 0 followed by its
+    /// length and bytes the first time
+    pub fn write_str(&mut self, v: &str) {
+        match self.strings.get(v) {
+            Some(index) => self.write_usize(index + 1),
+            None => {
+                self.strings.insert(Rc::from(v), self.strings.len());
+                self.write_usize(0);
+                self.write_usize(v.len());
+                self.bytes.extend_from_slice(v.as_bytes());
+            }
+        }
+    }
+
+    pub fn write_statements(&mut self, statements: &[Statement]) {
+        self.write_usize(statements.len());
+        for statement in statements {
+            statement.accept(self);
+        }
+    }
+
+    fn write_id(&mut self, id: NodeId) {
+        self.write_usize(id.0);
+    }
+
+    fn write_span(&mut self, span: Span) {
+        self.write_usize(span.start);
+        self.write_usize(span.end);
+        self.write_usize(span.start_line);
+        self.write_usize(span.end_line);
+    }
+
+    fn write_token(&mut self, token: &Token) {
+        self.write_u8(token.token_type as u8);
+        self.write_str(&token.lexeme);
+        self.write_usize(token.line);
+        self.write_span(token.span);
+    }
+}
+
+impl ExprVisitor for BinaryWriter {
+    type Return = ();
+
+    fn visit_unary(&mut self, unary: &Unary) -> Self::Return {
+        self.write_u8(0);
+        self.write_id(unary.id);
+        self.write_span(unary.span);
+        self.write_token(&unary.op);
+        unary.expr.accept(self);
+    }
+
+    fn visit_binary(&mut self, binary: &Binary) -> Self::Return {
+        self.write_u8(1);
+        self.write_id(binary.id);
+        self.write_span(binary.span);
+        binary.left.accept(self);
+        self.write_token(&binary.op);
+        binary.right.accept(self);
+    }
+
+    fn visit_grouping(&mut self, grouping: &Grouping) -> Self::Return {
+        self.write_u8(2);
+        self.write_id(grouping.id);
+        self.write_span(grouping.span);
+        grouping.expr.accept(self);
+    }
+
+    fn visit_literal(&mut self, literal: &Literal) -> Self::Return {
+        self.write_u8(3);
+        self.write_id(literal.id);
+        self.write_span(literal.span);
+        match &literal.value {
+            LiteralValue::Nil => self.write_u8(0),
+            LiteralValue::Boolean(false) => self.write_u8(1),
+            LiteralValue::Boolean(true) => self.write_u8(2),
+            LiteralValue::Number(v) => {
+                self.write_u8(3);
+                self.write_f64(*v);
+            }
+            LiteralValue::String(v) => {
+                self.write_u8(4);
+                self.write_str(v);
+            }
+        }
+    }
+
+    fn visit_variable(&mut self, variable: &Variable) -> Self::Return {
+        self.write_u8(4);
+        self.write_id(variable.id);
+        self.write_span(variable.span);
+        self.write_token(&variable.name);
+    }
+
+    fn visit_assign(&mut self, assign: &Assign) -> Self::Return {
+        self.write_u8(5);
+        self.write_id(assign.id);
+        self.write_span(assign.span);
+        self.write_token(&assign.name);
+        assign.value.accept(self);
+    }
+
+    fn visit_error(&mut self, error: &ErrorExpr) -> Self::Return {
+        self.write_u8(6);
+        self.write_id(error.id);
+        self.write_span(error.span);
+    }
+}
+
+impl StatementVisitor for BinaryWriter {
+    type Return = ();
+
+    fn visit_expression(&mut self, statement: &ExpressionStatement) -> Self::Return {
+        self.write_u8(0);
+        self.write_id(statement.id);
+        self.write_span(statement.span);
+        statement.expr.accept(self);
+    }
+
+    fn visit_print(&mut self, statement: &PrintStatement) -> Self::Return {
+        self.write_u8(1);
+        self.write_id(statement.id);
+        self.write_span(statement.span);
+        statement.expr.accept(self);
+    }
+
+    fn visit_variable(&mut self, variable: &VariableStatement) -> Self::Return {
+        self.write_u8(2);
+        self.write_id(variable.id);
+        self.write_span(variable.span);
+        self.write_token(&variable.name);
+        match variable.initializer.as_ref() {
+            Some(initializer) => {
+                self.write_u8(1);
+                initializer.accept(self);
+            }
+            None => self.write_u8(0),
+        }
+    }
+
+    fn visit_block(&mut self, block: &BlockStatement) -> Self::Return {
+        self.write_u8(3);
+        self.write_id(block.id);
+        self.write_span(block.span);
+        self.write_statements(&block.statements);
+    }
+
+    fn visit_error(&mut self, error: &ErrorStatement) -> Self::Return {
+        self.write_u8(4);
+        self.write_id(error.id);
+        self.write_span(error.span);
+    }
+}
+
+/// Decoder of the representation written by [`BinaryWriter`]
+pub struct BinaryReader<'a> {
+    bytes: &'a [u8],
+    position: usize,
+    /// Strings read so far, by index, interned like the ones of the scanner
+    strings: Vec<Rc<str>>,
+}
+
+impl<'a> BinaryReader<'a> {
+    pub fn new(bytes: &'a [u8]) -> Self {
+        Self {
+            bytes,
+            position: 0,
+            strings: Vec::new(),
+        }
+    }
+
+    /// Whether every byte was read
+    pub fn is_at_end(&self) -> bool {
+        self.position == self.bytes.len()
+    }
+
+    pub fn read_u8(&mut self) -> BinaryResult<u8> {
+        let v = *self
+            .bytes
+            .get(self.position)
+            .ok_or_else(|| self.error("unexpected end of input"))?;
+        self.position += 1;
+        Ok(v)
+    }
+
+    pub fn read_usize(&mut self) -> BinaryResult<usize> {
+        let mut v = 0;
+        let mut shift = 0;
+        loop {
+            let byte = self.read_u8()?;
+            if shift >= usize::BITS {
+                return Err(self.error("integer overflow"));
+            }
+            v |= ((byte & 0x7f) as usize) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(v);
+            }
+            shift += 7;
+        }
+    }
+
+    pub fn read_f64(&mut self) -> BinaryResult<f64> {
+        let bytes = self.read_bytes(8)?;
+        Ok(f64::from_le_bytes(bytes.try_into().unwrap()))
+    }
+
+    pub fn read_str(&mut self) -> BinaryResult<Rc<str>> {
+        match self.read_usize()? {
+            0 => {
+                let len = self.read_usize()?;
+                let bytes = self.read_bytes(len)?;
+                let v = std::str::from_utf8(bytes).map_err(|_| self.error("invalid UTF-8"))?;
+                let v = interner::intern(v);
+                self.strings.push(v.clone());
+                Ok(v)
+            }
+            index => self
+                .strings
+                .get(index - 1)
+                .cloned()
+                .ok_or_else(|| self.error(&format!("unknown string {}", index - 1))),
+        }
+    }
+
+    /// Read a program, allocating its expressions in `arena`
+    pub fn read_statements<'b>(
+        &mut self,
+        arena: &'b Arena<'b>,
+    ) -> BinaryResult<Vec<Statement<'b>>> {
+        let len = self.read_usize()?;
+        // the length isn't trusted to allocate, a corrupted one could be huge
+        let mut statements = Vec::new();
+        for _ in 0..len {
+            statements.push(self.read_statement(arena)?);
+        }
+        Ok(statements)
+    }
+
+    fn read_statement<'b>(&mut self, arena: &'b Arena<'b>) -> BinaryResult<Statement<'b>> {
+        let tag = self.read_u8()?;
+        let id = self.read_id()?;
+        let span = self.read_span()?;
+        let statement = match tag {
+            0 => Statement::Expression(ExpressionStatement {
+                id,
+                span,
+                expr: self.read_expr(arena)?,
+            }),
+            1 => Statement::Print(PrintStatement {
+                id,
+                span,
+                expr: self.read_expr(arena)?,
+            }),
+            2 => {
+                let name = self.read_token()?;
+                let initializer = match self.read_u8()? {
+                    0 => None,
+                    1 => Some(self.read_expr(arena)?),
+                    tag => return Err(self.error(&format!("invalid initializer tag {tag}"))),
+                };
+                Statement::Variable(VariableStatement {
+                    id,
+                    span,
+                    name,
+                    initializer,
+                })
+            }
+            3 => Statement::Block(BlockStatement {
+                id,
+                span,
+                statements: self.read_statements(arena)?,
+            }),
+            4 => Statement::Error(ErrorStatement { id, span }),
+            tag => return Err(self.error(&format!("invalid statement tag {tag}"))),
+        };
+        Ok(statement)
+    }
+
+    fn read_expr<'b>(&mut self, arena: &'b Arena<'b>) -> BinaryResult<Expr<'b>> {
+        let tag = self.read_u8()?;
+        let id = self.read_id()?;
+        let span = self.read_span()?;
+        let expr = match tag {
+            0 => Expr::Unary(Unary {
+                id,
+                span,
+                op: self.read_token()?,
+                expr: arena.alloc(self.read_expr(arena)?),
+            }),
+            1 => Expr::Binary(Binary {
+                id,
+                span,
+                left: arena.alloc(self.read_expr(arena)?),
+                op: self.read_token()?,
+                right: arena.alloc(self.read_expr(arena)?),
+            }),
+            2 => Expr::Grouping(Grouping {
+                id,
+                span,
+                expr: arena.alloc(self.read_expr(arena)?),
+            }),
+            3 => Expr::Literal(Literal {
+                id,
+                span,
+                value: self.read_literal_value()?,
+            }),
+            4 => Expr::Variable(Variable {
+                id,
+                span,
+                name: self.read_token()?,
+            }),
+            5 => Expr::Assign(Assign {
+                id,
+                span,
+                name: self.read_token()?,
+                value: arena.alloc(self.read_expr(arena)?),
+            }),
+            6 => Expr::Error(ErrorExpr { id, span }),
+            tag => return Err(self.error(&format!("invalid expression tag {tag}"))),
+        };
+        Ok(expr)
+    }
+
+    fn read_literal_value(&mut self) -> BinaryResult<LiteralValue> {
+        match self.read_u8()? {
+            0 => Ok(LiteralValue::Nil),
+            1 => Ok(LiteralValue::Boolean(false)),
+            2 => Ok(LiteralValue::Boolean(true)),
+            3 => Ok(LiteralValue::Number(self.read_f64()?)),
+            4 => Ok(LiteralValue::String(self.read_str()?)),
+            tag => Err(self.error(&format!("invalid literal tag {tag}"))),
+        }
+    }
+
+    fn read_id(&mut self) -> BinaryResult<NodeId> {
+        Ok(NodeId(self.read_usize()?))
+    }
+
+    fn read_span(&mut self) -> BinaryResult<Span> {
+        Ok(Span::new(
+            self.read_usize()?,
+            self.read_usize()?,
+            self.read_usize()?,
+            self.read_usize()?,
+        ))
+    }
+
+    fn read_token(&mut self) -> BinaryResult<Token> {
+        let tag = self.read_u8()?;
+        let token_type = *TOKEN_TYPES
+            .get(tag as usize)
+            .ok_or_else(|| self.error(&format!("invalid token type {tag}")))?;
+        Ok(Token::new(
+            token_type,
+            self.read_str()?,
+            self.read_usize()?,
+            self.read_span()?,
+        ))
+    }
+
+    fn read_bytes(&mut self, len: usize) -> BinaryResult<&'a [u8]> {
+        let end = self
+            .position
+            .checked_add(len)
+            .filter(|end| *end <= self.bytes.len())
+            .ok_or_else(|| self.error("unexpected end of input"))?;
+        let bytes = &self.bytes[self.position..end];
+        self.position = end;
+        Ok(bytes)
+    }
+
+    /// Error at the current position of the reader
+    pub fn error(&self, msg: &str) -> BinaryError {
+        BinaryError::new(self.position, msg.to_owned())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{BinaryReader, BinaryWriter, TOKEN_TYPES};
+    use crate::{
+        ast::{Arena, Statement},
+        parser::Parser,
+        scanner::Scanner,
+    };
+
+    fn parse<'a>(source: &str, arena: &'a Arena<'a>) -> Vec<Statement<'a>> {
+        let tokens = Scanner::new(source).scan_tokens().unwrap();
+        Parser::new(tokens, arena).parse().unwrap()
+    }
+
+    fn encode(statements: &[Statement]) -> Vec<u8> {
+        let mut writer = BinaryWriter::new();
+        writer.write_statements(statements);
+        writer.into_bytes()
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let arena = Arena::new();
+        let ast = parse(
+            "var a = (1.5 + 2) * -3 >= 4 == !false;
+            var b;
+            { var a = \"x\" + \"\u{e9}\" != nil; print a; b = a; }
+            print b; print true; 1234567;",
+            &arena,
+        );
+        let bytes = encode(&ast);
+
+        let mut reader = BinaryReader::new(&bytes);
+        assert_eq!(reader.read_statements(&arena).unwrap(), ast);
+        assert!(reader.is_at_end());
+    }
+
+    #[test]
+    fn test_round_trip_error_nodes() {
+        let tokens = Scanner::new("print (1 +); var = 2;").scan_tokens().unwrap();
+        let arena = Arena::new();
+        let (ast, _) = Parser::new(tokens, &arena).parse_partial();
+        let bytes = encode(&ast);
+
+        assert_eq!(
+            BinaryReader::new(&bytes).read_statements(&arena).unwrap(),
+            ast
+        );
+    }
+
+    #[test]
+    fn test_strings_are_written_once() {
+        let statement = format!("print {};", "a".repeat(100));
+        let arena = Arena::new();
+        let once = encode(&parse(&statement, &arena)).len();
+        let twice = encode(&parse(&statement.repeat(2), &arena)).len();
+        assert!(twice - once < 100);
+    }
+
+    #[test]
+    fn test_truncated_input() {
+        let arena = Arena::new();
+        let bytes = encode(&parse("var a = 1; { print a; }", &arena));
+        for len in 0..bytes.len() {
+            assert!(BinaryReader::new(&bytes[..len])
+                .read_statements(&arena)
+                .is_err());
+        }
+    }
+
+    #[test]
+    fn test_token_types() {
+        for (i, token_type) in TOKEN_TYPES.iter().enumerate() {
+            assert_eq!(*token_type as usize, i);
+        }
+    }
+}
@@ -1,48 +1,90 @@
-use super::visitor::ExprVisitor;
-use crate::token::Token;
-
-/// Base structure of the AST
-pub enum Expr {
-    Unary(Unary),
-    Binary(Binary),
-    Grouping(Grouping),
+use std::rc::Rc;
+
+use serde::{Deserialize, Serialize};
+
+use super::{visitor::ExprVisitor, Arena, NodeId};
+use crate::{
+    interner,
+    token::{Span, Token},
+};
+
+/// Base structure of the AST, whose children live in an [`Arena`]
+#[derive(Debug, PartialEq, Clone, Serialize)]
+#[serde(tag = "type")]
+pub enum Expr<'a> {
+    Unary(Unary<'a>),
+    Binary(Binary<'a>),
+    Grouping(Grouping<'a>),
     Literal(Literal),
     Variable(Variable),
-    Assign(Assign),
+    Assign(Assign<'a>),
+    /// Placeholder for an expression which failed to parse
+    Error(ErrorExpr),
 }
 
-pub struct Unary {
+#[derive(Debug, PartialEq, Clone, Serialize)]
+pub struct Unary<'a> {
+    pub id: NodeId,
+    pub span: Span,
     pub op: Token,
-    pub expr: Box<Expr>,
+    pub expr: &'a Expr<'a>,
 }
 
-pub struct Binary {
-    pub left: Box<Expr>,
+#[derive(Debug, PartialEq, Clone, Serialize)]
+pub struct Binary<'a> {
+    pub id: NodeId,
+    pub span: Span,
+    pub left: &'a Expr<'a>,
     pub op: Token,
-    pub right: Box<Expr>,
+    pub right: &'a Expr<'a>,
 }
-pub struct Grouping {
-    pub expr: Box<Expr>,
+
+#[derive(Debug, PartialEq, Clone, Serialize)]
+pub struct Grouping<'a> {
+    pub id: NodeId,
+    pub span: Span,
+    pub expr: &'a Expr<'a>,
+}
+
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct Literal {
+    pub id: NodeId,
+    pub span: Span,
+    pub value: LiteralValue,
 }
 
-#[derive(Debug)]
-pub enum Literal {
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", content = "value")]
+pub enum LiteralValue {
     Boolean(bool),
-    String(String),
+    String(#[serde(deserialize_with = "interner::deserialize")] Rc<str>),
     Nil,
     Number(f64),
 }
 
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
 pub struct Variable {
+    pub id: NodeId,
+    pub span: Span,
     pub name: Token,
 }
 
-pub struct Assign {
+#[derive(Debug, PartialEq, Clone, Serialize)]
+pub struct Assign<'a> {
+    pub id: NodeId,
+    pub span: Span,
     pub name: Token,
-    pub value: Box<Expr>,
+    pub value: &'a Expr<'a>,
 }
 
-impl Expr {
+/// Tokens that couldn't be parsed as an expression, see [`crate::parser::Parser::parse_partial`]
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct ErrorExpr {
+    pub id: NodeId,
+    pub span: Span,
+}
+
+impl<'a> Expr<'a> {
     pub fn accept<T>(&self, visitor: &mut dyn ExprVisitor<Return = T>) -> T {
         match self {
             Expr::Unary(unary) => visitor.visit_unary(unary),
@@ -51,61 +93,120 @@ impl Expr {
             Expr::Literal(literal) => visitor.visit_literal(literal),
             Expr::Variable(variable) => visitor.visit_variable(variable),
             Expr::Assign(assign) => visitor.visit_assign(assign),
+            Expr::Error(error) => visitor.visit_error(error),
         }
     }
 
-    /// Helper function to generate a binary expression instance
-    pub fn new_binary(left: Expr, op: Token, right: Expr) -> Self {
+    /// Identity of the node
+    #[allow(unused)]
+    pub fn id(&self) -> NodeId {
+        match self {
+            Expr::Unary(v) => v.id,
+            Expr::Binary(v) => v.id,
+            Expr::Grouping(v) => v.id,
+            Expr::Literal(v) => v.id,
+            Expr::Variable(v) => v.id,
+            Expr::Assign(v) => v.id,
+            Expr::Error(v) => v.id,
+        }
+    }
+
+    /// Region of the source the expression was parsed from
+    pub fn span(&self) -> Span {
+        match self {
+            Expr::Unary(v) => v.span,
+            Expr::Binary(v) => v.span,
+            Expr::Grouping(v) => v.span,
+            Expr::Literal(v) => v.span,
+            Expr::Variable(v) => v.span,
+            Expr::Assign(v) => v.span,
+            Expr::Error(v) => v.span,
+        }
+    }
+
+    /// Helper function to generate a binary expression instance, spanning from its left to its
+    /// right operand
+    pub fn new_binary(arena: &'a Arena<'a>, left: Expr<'a>, op: Token, right: Expr<'a>) -> Self {
         Expr::Binary(Binary {
-            left: Box::new(left),
+            id: NodeId::next(),
+            span: left.span().to(right.span()),
+            left: arena.alloc(left),
             op,
-            right: Box::new(right),
+            right: arena.alloc(right),
         })
     }
 
-    /// Helper function to generate a unary expression instance
-    pub fn new_unary(op: Token, expr: Expr) -> Self {
+    /// Helper function to generate a unary expression instance, spanning from its operator to its
+    /// operand
+    pub fn new_unary(arena: &'a Arena<'a>, op: Token, expr: Expr<'a>) -> Self {
         Expr::Unary(Unary {
+            id: NodeId::next(),
+            span: op.span.to(expr.span()),
             op,
-            expr: Box::new(expr),
+            expr: arena.alloc(expr),
         })
     }
 
     /// Helper function to generate boolean literal expression instance
-    pub fn new_boolean_literal(v: bool) -> Self {
-        Expr::Literal(Literal::Boolean(v))
+    pub fn new_boolean_literal(v: bool, span: Span) -> Self {
+        Self::new_literal(LiteralValue::Boolean(v), span)
     }
 
     /// Helper function to generate boolean literal expression instance
-    pub fn new_nil_literal() -> Self {
-        Expr::Literal(Literal::Nil)
+    pub fn new_nil_literal(span: Span) -> Self {
+        Self::new_literal(LiteralValue::Nil, span)
     }
 
     /// Helper function to generate boolean literal expression instance
-    pub fn new_number_literal(v: f64) -> Self {
-        Expr::Literal(Literal::Number(v))
+    pub fn new_number_literal(v: f64, span: Span) -> Self {
+        Self::new_literal(LiteralValue::Number(v), span)
     }
 
     /// Helper function to generate boolean literal expression instance
-    pub fn new_string_literal(v: String) -> Self {
-        Expr::Literal(Literal::String(v))
+    pub fn new_string_literal(v: Rc<str>, span: Span) -> Self {
+        Self::new_literal(LiteralValue::String(v), span)
+    }
+
+    /// Helper function to generate a literal expression instance
+    pub fn new_literal(value: LiteralValue, span: Span) -> Self {
+        Expr::Literal(Literal {
+            id: NodeId::next(),
+            span,
+            value,
+        })
     }
 
-    /// Helper function to generate a grouping expression instance
-    pub fn new_grouping(expr: Expr) -> Self {
+    /// Helper function to generate a grouping expression instance, the span should include the
+    /// parentheses
+    pub fn new_grouping(arena: &'a Arena<'a>, expr: Expr<'a>, span: Span) -> Self {
         Expr::Grouping(Grouping {
-            expr: Box::new(expr),
+            id: NodeId::next(),
+            span,
+            expr: arena.alloc(expr),
         })
     }
 
     pub fn new_variable(name: Token) -> Self {
-        Expr::Variable(Variable { name })
+        Expr::Variable(Variable {
+            id: NodeId::next(),
+            span: name.span,
+            name,
+        })
     }
 
-    pub fn new_assign(name: Token, value: Expr) -> Self {
+    pub fn new_assign(arena: &'a Arena<'a>, name: Token, value: Expr<'a>) -> Self {
         Expr::Assign(Assign {
+            id: NodeId::next(),
+            span: name.span.to(value.span()),
             name,
-            value: Box::new(value),
+            value: arena.alloc(value),
+        })
+    }
+
+    pub fn new_error(span: Span) -> Self {
+        Expr::Error(ErrorExpr {
+            id: NodeId::next(),
+            span,
         })
     }
 }
@@ -0,0 +1,251 @@
+use serde::de::{DeserializeOwned, Error};
+use serde_json::{Map, Value};
+
+use super::{
+    expression::{Assign, Binary, Grouping, Unary},
+    statement::{BlockStatement, ExpressionStatement, PrintStatement, VariableStatement},
+    Arena,
+    Expr,
+    NodeId,
+    Statement,
+};
+
+/// Serialize a program into its JSON representation.
+///
+/// Every node is an object tagged by its `type` (`kind` for literal values) holding its identity
+/// and span, tokens keep their type, lexeme, line and span, so the output is enough to rebuild the
+/// exact same AST with [`from_json`].
+pub fn to_json(statements: &[Statement]) -> serde_json::Result<String> {
+    serde_json::to_string_pretty(statements)
+}
+
+/// Rebuild a program from the JSON representation produced by [`to_json`], allocating its
+/// expressions in `arena`. The identities of its nodes must be unique, as the interpreter keeps
+/// its side tables about nodes by identity.
+pub fn from_json<'a>(json: &str, arena: &'a Arena<'a>) -> serde_json::Result<Vec<Statement<'a>>> {
+    let statements = Rebuilder { arena }.statements(serde_json::from_str(json)?)?;
+    NodeId::claim(&statements)
+        .map_err(|id| serde_json::Error::custom(format!("duplicate node id {}", id.0)))?;
+    Ok(statements)
+}
+
+/// Types of the nodes, for the errors about unknown ones
+const STATEMENTS: &[&str] = &["Expression", "Print", "Variable", "Block", "Error"];
+const EXPRESSIONS: &[&str] = &[
+    "Unary", "Binary", "Grouping", "Literal", "Variable", "Assign", "Error",
+];
+
+/// Builds the nodes of a JSON document, the ones without children being deserialized as they
+/// derive it while the children of the others are allocated in the arena
+struct Rebuilder<'a> {
+    arena: &'a Arena<'a>,
+}
+
+impl<'a> Rebuilder<'a> {
+    fn statements(&self, value: Value) -> serde_json::Result<Vec<Statement<'a>>> {
+        match value {
+            Value::Array(values) => values.into_iter().map(|v| self.statement(v)).collect(),
+            _ => Err(Error::custom("expected a list of statements")),
+        }
+    }
+
+    fn statement(&self, value: Value) -> serde_json::Result<Statement<'a>> {
+        let (tag, mut node) = tagged(value)?;
+        let statement = match tag.as_str() {
+            "Expression" => Statement::Expression(ExpressionStatement {
+                id: field(&mut node, "id")?,
+                span: field(&mut node, "span")?,
+                expr: self.expr(take(&mut node, "expr")?)?,
+            }),
+            "Print" => Statement::Print(PrintStatement {
+                id: field(&mut node, "id")?,
+                span: field(&mut node, "span")?,
+                expr: self.expr(take(&mut node, "expr")?)?,
+            }),
+            "Variable" => Statement::Variable(VariableStatement {
+                id: field(&mut node, "id")?,
+                span: field(&mut node, "span")?,
+                name: field(&mut node, "name")?,
+                initializer: match node.remove("initializer") {
+                    None | Some(Value::Null) => None,
+                    Some(initializer) => Some(self.expr(initializer)?),
+                },
+            }),
+            "Block" => Statement::Block(BlockStatement {
+                id: field(&mut node, "id")?,
+                span: field(&mut node, "span")?,
+                statements: self.statements(take(&mut node, "statements")?)?,
+            }),
+            "Error" => Statement::Error(serde_json::from_value(Value::Object(node))?),
+            tag => return Err(Error::unknown_variant(tag, STATEMENTS)),
+        };
+        Ok(statement)
+    }
+
+    fn expr(&self, value: Value) -> serde_json::Result<Expr<'a>> {
+        let (tag, mut node) = tagged(value)?;
+        let expr = match tag.as_str() {
+            "Unary" => Expr::Unary(Unary {
+                id: field(&mut node, "id")?,
+                span: field(&mut node, "span")?,
+                op: field(&mut node, "op")?,
+                expr: self.child(&mut node, "expr")?,
+            }),
+            "Binary" => Expr::Binary(Binary {
+                id: field(&mut node, "id")?,
+                span: field(&mut node, "span")?,
+                left: self.child(&mut node, "left")?,
+                op: field(&mut node, "op")?,
+                right: self.child(&mut node, "right")?,
+            }),
+            "Grouping" => Expr::Grouping(Grouping {
+                id: field(&mut node, "id")?,
+                span: field(&mut node, "span")?,
+                expr: self.child(&mut node, "expr")?,
+            }),
+            "Literal" => Expr::Literal(serde_json::from_value(Value::Object(node))?),
+            "Variable" => Expr::Variable(serde_json::from_value(Value::Object(node))?),
+            "Assign" => Expr::Assign(Assign {
+                id: field(&mut node, "id")?,
+                span: field(&mut node, "span")?,
+                name: field(&mut node, "name")?,
+                value: self.child(&mut node, "value")?,
+            }),
+            "Error" => Expr::Error(serde_json::from_value(Value::Object(node))?),
+            tag => return Err(Error::unknown_variant(tag, EXPRESSIONS)),
+        };
+        Ok(expr)
+    }
+
+    fn child(
+        &self,
+        node: &mut Map<String, Value>,
+        key: &'static str,
+    ) -> serde_json::Result<&'a Expr<'a>> {
+        Ok(self.arena.alloc(self.expr(take(node, key)?)?))
+    }
+}
+
+/// Split a node into its `type` and its other fields
+fn tagged(value: Value) -> serde_json::Result<(String, Map<String, Value>)> {
+    let Value::Object(mut node) = value else {
+        return Err(Error::custom("expected a node"));
+    };
+    match node.remove("type") {
+        Some(Value::String(tag)) => Ok((tag, node)),
+        _ => Err(Error::missing_field("type")),
+    }
+}
+
+fn take(node: &mut Map<String, Value>, key: &'static str) -> serde_json::Result<Value> {
+    node.remove(key).ok_or_else(|| Error::missing_field(key))
+}
+
+fn field<T: DeserializeOwned>(
+    node: &mut Map<String, Value>,
+    key: &'static str,
+) -> serde_json::Result<T> {
+    serde_json::from_value(take(node, key)?)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{from_json, to_json};
+    use crate::{
+        ast::{Arena, NodeId},
+        parser::Parser,
+        scanner::Scanner,
+    };
+
+    fn round_trip(source: &str) {
+        let tokens = Scanner::new(source).scan_tokens().unwrap();
+        let arena = Arena::new();
+        let ast = Parser::new(tokens, &arena).parse().unwrap();
+
+        let json = to_json(&ast).unwrap();
+        let rebuilt = from_json(&json, &arena).unwrap();
+        assert_eq!(to_json(&rebuilt).unwrap(), json);
+    }
+
+    #[test]
+    fn test_round_trip_expressions() {
+        round_trip("-123 * (45.67 + 2) >= 3 == !false;");
+        round_trip("\"a\" + \"b\" != nil;");
+    }
+
+    #[test]
+    fn test_round_trip_statements() {
+        round_trip(
+            "var a = 1;
+            var b;
+            { var a = a + 2; print a; b = a; }
+            print b;",
+        );
+    }
+
+    #[test]
+    fn test_json_shape() {
+        let tokens = Scanner::new("print 1;").scan_tokens().unwrap();
+        let arena = Arena::new();
+        let ast = Parser::new(tokens, &arena).parse().unwrap();
+        let value: serde_json::Value = serde_json::from_str(&to_json(&ast).unwrap()).unwrap();
+
+        assert_eq!(value[0]["type"], "Print");
+        assert_eq!(
+            value[0]["span"],
+            serde_json::json!({ "start": 0, "end": 8, "start_line": 0, "end_line": 0 })
+        );
+        assert_eq!(value[0]["expr"]["type"], "Literal");
+        assert_eq!(
+            value[0]["expr"]["value"],
+            serde_json::json!({ "kind": "Number", "value": 1.0 })
+        );
+        assert!(value[0]["id"].is_u64());
+    }
+
+    #[test]
+    fn test_rebuilt_ast_is_equal() {
+        let tokens = Scanner::new("var a = (1 + 2) * -3; { print a; }")
+            .scan_tokens()
+            .unwrap();
+        let arena = Arena::new();
+        let ast = Parser::new(tokens, &arena).parse().unwrap();
+
+        assert_eq!(from_json(&to_json(&ast).unwrap(), &arena).unwrap(), ast);
+    }
+
+    #[test]
+    fn test_duplicate_ids_are_rejected() {
+        let tokens = Scanner::new("var g = 5; print g; { var a = 1; }")
+            .scan_tokens()
+            .unwrap();
+        let arena = Arena::new();
+        let ast = Parser::new(tokens, &arena).parse().unwrap();
+        let json = to_json(&ast).unwrap();
+        let mut value: serde_json::Value = serde_json::from_str(&json).unwrap();
+        value[1]["expr"]["id"] = value[0]["id"].clone();
+
+        let error = from_json(&value.to_string(), &arena).unwrap_err();
+        assert!(error
+            .to_string()
+            .starts_with(&format!("duplicate node id {}", value[0]["id"])));
+    }
+
+    #[test]
+    fn test_invalid_nodes_are_rejected() {
+        let arena = Arena::new();
+        let error = from_json(r#"[{ "type": "Return", "id": 0 }]"#, &arena).unwrap_err();
+        assert!(error.to_string().starts_with("unknown variant `Return`"));
+
+        let json = r#"[{ "type": "Print", "id": 0, "span": { "start": 0, "end": 1, "start_line": 0, "end_line": 0 } }]"#;
+        let error = from_json(json, &arena).unwrap_err();
+        assert_eq!(error.to_string(), "missing field `expr`");
+    }
+
+    #[test]
+    fn test_loaded_ids_are_claimed() {
+        let json = r#"[{ "type": "Error", "id": 1000000, "span": { "start": 0, "end": 1, "start_line": 0, "end_line": 0 } }]"#;
+        from_json(json, &Arena::new()).unwrap();
+        assert!(NodeId::next() > NodeId(1_000_000));
+    }
+}
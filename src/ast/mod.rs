@@ -1,6 +1,12 @@
+mod arena;
+pub mod binary;
 pub mod expression;
+pub mod json;
+mod node_id;
 pub mod statement;
 pub mod visitor;
 
+pub use arena::Arena;
 pub use expression::Expr;
+pub use node_id::{NodeId, NodeMap};
 pub use statement::Statement;
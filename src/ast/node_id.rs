@@ -0,0 +1,150 @@
+use std::{
+    collections::HashMap,
+    hash::{BuildHasherDefault, Hasher},
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use serde::{Deserialize, Serialize};
+
+use super::{
+    expression::{Assign, Binary, ErrorExpr, Grouping, Literal, Unary, Variable},
+    statement::{
+        BlockStatement,
+        ErrorStatement,
+        ExpressionStatement,
+        PrintStatement,
+        VariableStatement,
+    },
+    visitor::walker::{self, Walker},
+    Statement,
+};
+
+/// Counter handing out node identities, shared by every parser of the process so that nodes of
+/// programs parsed one after another (e.g. in the REPL) never share an identity
+static NEXT_NODE_ID: AtomicUsize = AtomicUsize::new(0);
+
+/// Unique identity of an AST node, meant to be used as key of side tables about nodes
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct NodeId(pub usize);
+
+impl NodeId {
+    /// Allocate a new identity, different from every other allocated one
+    pub fn next() -> Self {
+        NodeId(NEXT_NODE_ID.fetch_add(1, Ordering::Relaxed))
+    }
+
+    /// Check that the nodes of a program built elsewhere than by the parser (e.g. loaded from a
+    /// file) have unique identities, returning a repeated one otherwise. The identities are then
+    /// taken, so that the nodes allocated afterwards never share one with them.
+    pub fn claim(statements: &[Statement]) -> Result<(), NodeId> {
+        let mut claimer = Claimer {
+            ids: NodeMap::default(),
+            duplicate: None,
+        };
+        for statement in statements {
+            claimer.visit_statement(statement);
+        }
+        if let Some(id) = claimer.duplicate {
+            return Err(id);
+        }
+        if let Some(last) = claimer.ids.keys().max() {
+            NEXT_NODE_ID.fetch_max(last.0 + 1, Ordering::Relaxed);
+        }
+        Ok(())
+    }
+}
+
+/// Collects the identities of the visited nodes, remembering the first one seen twice
+struct Claimer {
+    ids: NodeMap<()>,
+    duplicate: Option<NodeId>,
+}
+
+impl Claimer {
+    fn claim(&mut self, id: NodeId) {
+        if self.ids.insert(id, ()).is_some() && self.duplicate.is_none() {
+            self.duplicate = Some(id);
+        }
+    }
+}
+
+impl Walker for Claimer {
+    fn visit_expression_statement(&mut self, statement: &ExpressionStatement) {
+        self.claim(statement.id);
+        walker::walk_expression_statement(self, statement)
+    }
+
+    fn visit_print_statement(&mut self, statement: &PrintStatement) {
+        self.claim(statement.id);
+        walker::walk_print_statement(self, statement)
+    }
+
+    fn visit_variable_statement(&mut self, statement: &VariableStatement) {
+        self.claim(statement.id);
+        walker::walk_variable_statement(self, statement)
+    }
+
+    fn visit_block_statement(&mut self, statement: &BlockStatement) {
+        self.claim(statement.id);
+        walker::walk_block_statement(self, statement)
+    }
+
+    fn visit_error_statement(&mut self, statement: &ErrorStatement) {
+        self.claim(statement.id);
+    }
+
+    fn visit_unary(&mut self, unary: &Unary) {
+        self.claim(unary.id);
+        walker::walk_unary(self, unary)
+    }
+
+    fn visit_binary(&mut self, binary: &Binary) {
+        self.claim(binary.id);
+        walker::walk_binary(self, binary)
+    }
+
+    fn visit_grouping(&mut self, grouping: &Grouping) {
+        self.claim(grouping.id);
+        walker::walk_grouping(self, grouping)
+    }
+
+    fn visit_literal(&mut self, literal: &Literal) {
+        self.claim(literal.id);
+    }
+
+    fn visit_variable(&mut self, variable: &Variable) {
+        self.claim(variable.id);
+    }
+
+    fn visit_assign(&mut self, assign: &Assign) {
+        self.claim(assign.id);
+        walker::walk_assign(self, assign)
+    }
+
+    fn visit_error_expr(&mut self, error: &ErrorExpr) {
+        self.claim(error.id);
+    }
+}
+
+/// Side table about nodes, keyed by their identity
+pub type NodeMap<V> = HashMap<NodeId, V, BuildHasherDefault<NodeIdHasher>>;
+
+/// Hasher of node identities: they're already unique integers, so they only need to be spread
+/// over the whole range of hashes instead of going through SipHash
+#[derive(Default)]
+pub struct NodeIdHasher(u64);
+
+impl Hasher for NodeIdHasher {
+    fn finish(&self) -> u64 {
+        self.0
+    }
+
+    fn write(&mut self, _bytes: &[u8]) {
+        unreachable!("NodeIdHasher only hashes node identities")
+    }
+
+    fn write_usize(&mut self, id: usize) {
+        // Fibonacci hashing, sets the high bits used by the map to pick buckets
+        self.0 = (id as u64).wrapping_mul(0x9E37_79B9_7F4A_7C15);
+    }
+}
@@ -1,51 +1,125 @@
-use super::{visitor::StatementVisitor, Expr};
-use crate::token::Token;
-
-pub enum Statement {
-    Expression(ExpressionStatement),
-    Print(PrintStatement),
-    Variable(VariableStatement),
-    Block(BlockStatement),
+use serde::{Deserialize, Serialize};
+
+use super::{visitor::StatementVisitor, Expr, NodeId};
+use crate::token::{Span, Token};
+
+#[derive(Debug, PartialEq, Clone, Serialize)]
+#[serde(tag = "type")]
+pub enum Statement<'a> {
+    Expression(ExpressionStatement<'a>),
+    Print(PrintStatement<'a>),
+    Variable(VariableStatement<'a>),
+    Block(BlockStatement<'a>),
+    /// Placeholder for a statement which failed to parse
+    Error(ErrorStatement),
 }
 
-pub struct ExpressionStatement {
-    pub expr: Expr,
+#[derive(Debug, PartialEq, Clone, Serialize)]
+pub struct ExpressionStatement<'a> {
+    pub id: NodeId,
+    pub span: Span,
+    pub expr: Expr<'a>,
 }
 
-pub struct PrintStatement {
-    pub expr: Expr,
+#[derive(Debug, PartialEq, Clone, Serialize)]
+pub struct PrintStatement<'a> {
+    pub id: NodeId,
+    pub span: Span,
+    pub expr: Expr<'a>,
 }
 
-pub struct VariableStatement {
+#[derive(Debug, PartialEq, Clone, Serialize)]
+pub struct VariableStatement<'a> {
+    pub id: NodeId,
+    pub span: Span,
     pub name: Token,
-    pub initializer: Option<Expr>,
+    pub initializer: Option<Expr<'a>>,
+}
+
+#[derive(Debug, PartialEq, Clone, Serialize)]
+pub struct BlockStatement<'a> {
+    pub id: NodeId,
+    pub span: Span,
+    pub statements: Vec<Statement<'a>>,
 }
 
-pub struct BlockStatement {
-    pub statements: Vec<Statement>,
+/// Tokens that couldn't be parsed as a statement, see [`crate::parser::Parser::parse_partial`]
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct ErrorStatement {
+    pub id: NodeId,
+    pub span: Span,
 }
 
-impl Statement {
+impl<'a> Statement<'a> {
     pub fn accept<T>(&self, visitor: &mut dyn StatementVisitor<Return = T>) -> T {
         match self {
             Statement::Expression(v) => visitor.visit_expression(v),
             Statement::Print(v) => visitor.visit_print(v),
             Statement::Variable(v) => visitor.visit_variable(v),
             Statement::Block(v) => visitor.visit_block(v),
+            Statement::Error(v) => visitor.visit_error(v),
         }
     }
-    pub fn new_expression_statement(expr: Expr) -> Self {
-        Self::Expression(ExpressionStatement { expr })
+
+    /// Identity of the node
+    #[allow(unused)]
+    pub fn id(&self) -> NodeId {
+        match self {
+            Statement::Expression(v) => v.id,
+            Statement::Print(v) => v.id,
+            Statement::Variable(v) => v.id,
+            Statement::Block(v) => v.id,
+            Statement::Error(v) => v.id,
+        }
     }
 
-    pub fn new_print_statement(expr: Expr) -> Self {
-        Self::Print(PrintStatement { expr })
+    /// Region of the source the statement was parsed from, including its delimiters
+    pub fn span(&self) -> Span {
+        match self {
+            Statement::Expression(v) => v.span,
+            Statement::Print(v) => v.span,
+            Statement::Variable(v) => v.span,
+            Statement::Block(v) => v.span,
+            Statement::Error(v) => v.span,
+        }
+    }
+
+    pub fn new_expression_statement(expr: Expr<'a>, span: Span) -> Self {
+        Self::Expression(ExpressionStatement {
+            id: NodeId::next(),
+            span,
+            expr,
+        })
     }
 
-    pub fn new_var_statement(name: Token, initializer: Option<Expr>) -> Self {
-        Self::Variable(VariableStatement { name, initializer })
+    pub fn new_print_statement(expr: Expr<'a>, span: Span) -> Self {
+        Self::Print(PrintStatement {
+            id: NodeId::next(),
+            span,
+            expr,
+        })
     }
-    pub fn new_block_statement(statements: Vec<Statement>) -> Self {
-        Self::Block(BlockStatement { statements })
+
+    pub fn new_var_statement(name: Token, initializer: Option<Expr<'a>>, span: Span) -> Self {
+        Self::Variable(VariableStatement {
+            id: NodeId::next(),
+            span,
+            name,
+            initializer,
+        })
+    }
+    pub fn new_block_statement(statements: Vec<Statement<'a>>, span: Span) -> Self {
+        Self::Block(BlockStatement {
+            id: NodeId::next(),
+            span,
+            statements,
+        })
+    }
+
+    pub fn new_error_statement(span: Span) -> Self {
+        Self::Error(ErrorStatement {
+            id: NodeId::next(),
+            span,
+        })
     }
 }
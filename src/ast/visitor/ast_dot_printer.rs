@@ -0,0 +1,215 @@
+use super::walker::{self, Walker};
+use crate::{
+    ast::{
+        expression::{Assign, Binary, ErrorExpr, Grouping, Literal, LiteralValue, Unary, Variable},
+        statement::{
+            BlockStatement,
+            ErrorStatement,
+            ExpressionStatement,
+            PrintStatement,
+            VariableStatement,
+        },
+        Statement,
+    },
+    token::Span,
+};
+
+/// Renders an AST as a Graphviz DOT graph.
+///
+/// Every visited element emits a node labelled with its line and linked to the node of its parent,
+/// the stack of open nodes is kept in `parents`. Block statements are rendered as clusters so that
+/// scopes are visible in the graph.
+#[derive(Default)]
+pub struct ASTDotPrinter {
+    output: String,
+    parents: Vec<usize>,
+    next_node: usize,
+    next_cluster: usize,
+    indent_lvl: usize,
+}
+
+impl Walker for ASTDotPrinter {
+    fn visit_expression_statement(&mut self, statement: &ExpressionStatement) {
+        self.node("expression", statement.span, |p| {
+            walker::walk_expression_statement(p, statement)
+        })
+    }
+
+    fn visit_print_statement(&mut self, statement: &PrintStatement) {
+        self.node("print", statement.span, |p| {
+            walker::walk_print_statement(p, statement)
+        })
+    }
+
+    fn visit_variable_statement(&mut self, statement: &VariableStatement) {
+        let label = format!("var {}", statement.name.lexeme);
+        self.node(&label, statement.span, |p| {
+            walker::walk_variable_statement(p, statement)
+        })
+    }
+
+    fn visit_block_statement(&mut self, statement: &BlockStatement) {
+        let cluster = self.next_cluster;
+        self.next_cluster += 1;
+
+        self.line(&format!("subgraph cluster_{cluster} {{"));
+        self.indent_lvl += 1;
+        self.line("label=\"block\";");
+        self.node("block", statement.span, |p| {
+            walker::walk_block_statement(p, statement)
+        });
+        self.indent_lvl -= 1;
+        self.line("}");
+    }
+
+    fn visit_error_statement(&mut self, statement: &ErrorStatement) {
+        self.node("error", statement.span, |_| ())
+    }
+
+    fn visit_unary(&mut self, unary: &Unary) {
+        self.node(&unary.op.lexeme, unary.span, |p| {
+            walker::walk_unary(p, unary)
+        })
+    }
+
+    fn visit_binary(&mut self, binary: &Binary) {
+        self.node(&binary.op.lexeme, binary.span, |p| {
+            walker::walk_binary(p, binary)
+        })
+    }
+
+    fn visit_grouping(&mut self, grouping: &Grouping) {
+        self.node("group", grouping.span, |p| {
+            walker::walk_grouping(p, grouping)
+        })
+    }
+
+    fn visit_literal(&mut self, literal: &Literal) {
+        let label = match &literal.value {
+            LiteralValue::Boolean(v) => format!("{v}"),
+            LiteralValue::String(v) => format!("\"{v}\""),
+            LiteralValue::Nil => "nil".to_owned(),
+            LiteralValue::Number(v) => format!("{v}"),
+        };
+        self.node(&label, literal.span, |_| ())
+    }
+
+    fn visit_variable(&mut self, variable: &Variable) {
+        self.node(&variable.name.lexeme, variable.span, |_| ())
+    }
+
+    fn visit_assign(&mut self, assign: &Assign) {
+        let label = format!("{} =", assign.name.lexeme);
+        self.node(&label, assign.span, |p| walker::walk_assign(p, assign))
+    }
+
+    fn visit_error_expr(&mut self, error: &ErrorExpr) {
+        self.node("error", error.span, |_| ())
+    }
+}
+
+impl ASTDotPrinter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Render an AST as a DOT graph rooted in a `program` node
+    pub fn print(mut self, statements: &[Statement]) -> String {
+        self.line("digraph ast {");
+        self.indent_lvl += 1;
+        self.line("node [shape=box];");
+        self.node("program", None, |p| {
+            for statement in statements {
+                p.visit_statement(statement);
+            }
+        });
+        self.indent_lvl -= 1;
+        self.line("}");
+        self.output
+    }
+
+    /// Emit a node labelled with the given text and the line it starts on, and its edge from the
+    /// current parent, then emit its children
+    fn node(
+        &mut self,
+        label: &str,
+        span: impl Into<Option<Span>>,
+        children: impl FnOnce(&mut Self),
+    ) {
+        let node = self.next_node;
+        self.next_node += 1;
+        let label = match span.into() {
+            Some(span) => format!("{label}\nline {}", span.start_line),
+            None => label.to_owned(),
+        };
+        self.line(&format!("n{node} [label=\"{}\"];", escape(&label)));
+        if let Some(parent) = self.parents.last() {
+            self.line(&format!("n{parent} -> n{node};"));
+        }
+
+        self.parents.push(node);
+        children(self);
+        self.parents.pop();
+    }
+
+    fn line(&mut self, line: &str) {
+        self.output.push_str(&"    ".repeat(self.indent_lvl));
+        self.output.push_str(line);
+        self.output.push('\n');
+    }
+}
+
+/// Escape a label so that it can be embedded in a double-quoted DOT string
+fn escape(label: &str) -> String {
+    let mut escaped = String::with_capacity(label.len());
+    for c in label.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod test {
+    use super::ASTDotPrinter;
+    use crate::{ast::Arena, parser::Parser, scanner::Scanner};
+
+    #[test]
+    fn basic_test() {
+        let tokens = Scanner::new("var a = 1 + 2;\n{ print \"a\"; }")
+            .scan_tokens()
+            .unwrap();
+        let arena = Arena::new();
+        let statements = Parser::new(tokens, &arena).parse().unwrap();
+
+        assert_eq!(
+            ASTDotPrinter::new().print(&statements),
+            r#"digraph ast {
+    node [shape=box];
+    n0 [label="program"];
+    n1 [label="var a\nline 0"];
+    n0 -> n1;
+    n2 [label="+\nline 0"];
+    n1 -> n2;
+    n3 [label="1\nline 0"];
+    n2 -> n3;
+    n4 [label="2\nline 0"];
+    n2 -> n4;
+    subgraph cluster_0 {
+        label="block";
+        n5 [label="block\nline 1"];
+        n0 -> n5;
+        n6 [label="print\nline 1"];
+        n5 -> n6;
+        n7 [label="\"a\"\nline 1"];
+        n6 -> n7;
+    }
+}
+"#
+        );
+    }
+}
@@ -1,94 +1,98 @@
-use std::ops::Deref;
-
-use super::{ExprVisitor, StatementVisitor};
+use super::walker::{self, Walker};
 use crate::ast::{
-    expression::{Binary, Expr, Grouping, Literal, Unary},
-    statement::{ExpressionStatement, PrintStatement},
+    expression::{Assign, Binary, ErrorExpr, Grouping, Literal, LiteralValue, Unary, Variable},
+    statement::{BlockStatement, ErrorStatement, PrintStatement, VariableStatement},
     Statement,
 };
 
+/// Renders an AST as a tree, one node per line
+#[derive(Default)]
 pub struct ASTPrettyPrinter {
+    lines: Vec<String>,
     indent_lvl: usize,
 }
 
-impl ExprVisitor for ASTPrettyPrinter {
-    type Return = String;
+impl Walker for ASTPrettyPrinter {
+    fn visit_print_statement(&mut self, statement: &PrintStatement) {
+        self.node("print", |p| walker::walk_print_statement(p, statement))
+    }
 
-    fn visit_unary(&mut self, unary: &Unary) -> Self::Return {
-        self.format(&unary.op.lexeme, std::slice::from_ref(&unary.expr))
+    fn visit_variable_statement(&mut self, statement: &VariableStatement) {
+        self.node(&format!("var {}", statement.name.lexeme), |p| {
+            walker::walk_variable_statement(p, statement)
+        })
     }
 
-    fn visit_binary(&mut self, binary: &Binary) -> Self::Return {
-        self.format(
-            &binary.op.lexeme,
-            &[binary.left.as_ref(), binary.right.as_ref()],
-        )
+    fn visit_block_statement(&mut self, statement: &BlockStatement) {
+        self.node("block", |p| walker::walk_block_statement(p, statement))
     }
 
-    fn visit_grouping(&mut self, grouping: &Grouping) -> Self::Return {
-        self.format("group", std::slice::from_ref(&grouping.expr))
+    fn visit_error_statement(&mut self, _statement: &ErrorStatement) {
+        self.node("error", |_| ())
     }
 
-    fn visit_literal(&mut self, literal: &Literal) -> Self::Return {
-        let mut output = String::new();
-        if self.indent_lvl > 0 {
-            output.push_str(&"│  ".repeat(self.indent_lvl - 1));
-            output.push_str("└─ ");
-        }
-        match literal {
-            Literal::Boolean(v) => output.push_str(&format!("{v}")),
-            Literal::String(v) => output.push_str(v),
-            Literal::Nil => output.push_str("nil"),
-            Literal::Number(v) => output.push_str(&format!("{v}")),
-        }
+    fn visit_unary(&mut self, unary: &Unary) {
+        self.node(&unary.op.lexeme, |p| walker::walk_unary(p, unary))
+    }
 
-        output
+    fn visit_binary(&mut self, binary: &Binary) {
+        self.node(&binary.op.lexeme, |p| walker::walk_binary(p, binary))
     }
-}
 
-impl StatementVisitor for ASTPrettyPrinter {
-    type Return = String;
+    fn visit_grouping(&mut self, grouping: &Grouping) {
+        self.node("group", |p| walker::walk_grouping(p, grouping))
+    }
+
+    fn visit_literal(&mut self, literal: &Literal) {
+        let label = match &literal.value {
+            LiteralValue::Boolean(v) => format!("{v}"),
+            LiteralValue::String(v) => v.to_string(),
+            LiteralValue::Nil => "nil".to_owned(),
+            LiteralValue::Number(v) => format!("{v}"),
+        };
+        self.node(&label, |_| ())
+    }
 
-    fn visit_print(&mut self, statement: &PrintStatement) -> Self::Return {
-        format! {"print {}", statement.expr.accept(self)}
+    fn visit_variable(&mut self, variable: &Variable) {
+        self.node(&variable.name.lexeme, |_| ())
     }
 
-    fn visit_expression(&mut self, statement: &ExpressionStatement) -> Self::Return {
-        statement.expr.accept(self).to_string()
+    fn visit_assign(&mut self, assign: &Assign) {
+        self.node(&format!("{} =", assign.name.lexeme), |p| {
+            walker::walk_assign(p, assign)
+        })
+    }
+
+    fn visit_error_expr(&mut self, _error: &ErrorExpr) {
+        self.node("error", |_| ())
     }
 }
 
 impl ASTPrettyPrinter {
     pub fn new() -> Self {
-        ASTPrettyPrinter { indent_lvl: 0 }
+        Self::default()
     }
     /// Render an AST in a pretty printed fashion String
-    pub fn print(&mut self, statements: &Vec<Statement>) -> String {
-        let mut s = String::new();
+    pub fn print(mut self, statements: &[Statement]) -> String {
         for statement in statements {
-            s += &statement.accept(self);
-            s += "\n";
+            self.visit_statement(statement);
         }
-        s
+        self.lines.join("\n")
     }
 
-    /// Helper function to properly indent levels of the AST
-    fn format(&mut self, op_name: &str, children: &[impl Deref<Target = Expr>]) -> String {
-        let mut output = String::new();
+    /// Helper function to print a node at the right indentation level, followed by its children
+    fn node(&mut self, label: &str, children: impl FnOnce(&mut Self)) {
+        let mut line = String::new();
         if self.indent_lvl > 0 {
-            output.push_str(&"│  ".repeat(self.indent_lvl - 1));
-            output.push_str("└─ ");
+            line.push_str(&"│  ".repeat(self.indent_lvl - 1));
+            line.push_str("└─ ");
         }
+        line.push_str(label);
+        self.lines.push(line);
 
-        output.push_str(op_name);
         self.indent_lvl += 1;
-        for expr in children {
-            output.push('\n');
-            output.push_str(&expr.accept(self));
-        }
+        children(self);
         self.indent_lvl -= 1;
-
-        output
     }
 }
 
@@ -96,28 +100,40 @@ impl ASTPrettyPrinter {
 mod test {
     use super::ASTPrettyPrinter;
     use crate::{
-        ast::{expression::Expr, Statement},
-        token::{Token, TokenType},
+        ast::{expression::Expr, Arena, Statement},
+        token::{Span, Token, TokenType},
     };
 
     #[test]
     fn basic_test() {
-        let statements = vec![Statement::new_expression_statement(Expr::new_binary(
-            Expr::new_unary(
+        let arena = Arena::new();
+        let statements = vec![Statement::new_expression_statement(
+            Expr::new_binary(
+                &arena,
+                Expr::new_unary(
+                    &arena,
+                    Token {
+                        token_type: TokenType::Minus,
+                        lexeme: "-".into(),
+                        line: 0,
+                        span: Span::new(0, 1, 0, 0),
+                    },
+                    Expr::new_number_literal(123.0, Span::new(1, 4, 0, 0)),
+                ),
                 Token {
-                    token_type: TokenType::Minus,
-                    lexeme: "-".into(),
+                    token_type: TokenType::Star,
+                    lexeme: "*".into(),
                     line: 0,
+                    span: Span::new(5, 6, 0, 0),
                 },
-                Expr::new_number_literal(123.0),
+                Expr::new_grouping(
+                    &arena,
+                    Expr::new_number_literal(45.67, Span::new(8, 13, 0, 0)),
+                    Span::new(7, 14, 0, 0),
+                ),
             ),
-            Token {
-                token_type: TokenType::Star,
-                lexeme: "*".into(),
-                line: 0,
-            },
-            Expr::new_grouping(Expr::new_number_literal(45.67)),
-        ))];
+            Span::new(0, 15, 0, 0),
+        )];
 
         assert_eq!(
             ASTPrettyPrinter::new().print(&statements),
@@ -0,0 +1,228 @@
+use crate::ast::{
+    expression::{Assign, Binary, ErrorExpr, Grouping, Literal, Unary, Variable},
+    statement::{
+        BlockStatement,
+        ErrorStatement,
+        ExpressionStatement,
+        PrintStatement,
+        VariableStatement,
+    },
+    Arena,
+    Expr,
+    Statement,
+};
+
+/// Rewriting traversal of the AST.
+///
+/// Every method takes ownership of a node and returns the node replacing it, which doesn't have
+/// to be of the same variant. Methods default to rebuilding the node from its folded children
+/// through the matching `fold_*` function, so an implementor only overrides the nodes it rewrites.
+/// Children are shared in the arena: a copy of them is folded, and the folded children are
+/// allocated in the arena of the folder.
+pub trait Folder<'a> {
+    /// Where the folded children are allocated
+    fn arena(&self) -> &'a Arena<'a>;
+
+    /// Fold any statement, dispatching to the method of its variant
+    fn fold_statement(&mut self, statement: Statement<'a>) -> Statement<'a> {
+        fold_statement(self, statement)
+    }
+    /// Fold an expression statement
+    fn fold_expression_statement(&mut self, statement: ExpressionStatement<'a>) -> Statement<'a> {
+        fold_expression_statement(self, statement)
+    }
+    /// Fold a print statement
+    fn fold_print_statement(&mut self, statement: PrintStatement<'a>) -> Statement<'a> {
+        fold_print_statement(self, statement)
+    }
+    /// Fold a variable statement
+    fn fold_variable_statement(&mut self, statement: VariableStatement<'a>) -> Statement<'a> {
+        fold_variable_statement(self, statement)
+    }
+    /// Fold a block statement
+    fn fold_block_statement(&mut self, statement: BlockStatement<'a>) -> Statement<'a> {
+        fold_block_statement(self, statement)
+    }
+    /// Fold a statement which failed to parse, it has no children
+    fn fold_error_statement(&mut self, statement: ErrorStatement) -> Statement<'a> {
+        Statement::Error(statement)
+    }
+
+    /// Fold any expression, dispatching to the method of its variant
+    fn fold_expr(&mut self, expr: Expr<'a>) -> Expr<'a> {
+        fold_expr(self, expr)
+    }
+    /// Fold an unary expression
+    fn fold_unary(&mut self, unary: Unary<'a>) -> Expr<'a> {
+        fold_unary(self, unary)
+    }
+    /// Fold a binary expression
+    fn fold_binary(&mut self, binary: Binary<'a>) -> Expr<'a> {
+        fold_binary(self, binary)
+    }
+    /// Fold a grouping expression
+    fn fold_grouping(&mut self, grouping: Grouping<'a>) -> Expr<'a> {
+        fold_grouping(self, grouping)
+    }
+    /// Fold a literal expression, it has no children
+    fn fold_literal(&mut self, literal: Literal) -> Expr<'a> {
+        Expr::Literal(literal)
+    }
+    /// Fold a variable expression, it has no children
+    fn fold_variable(&mut self, variable: Variable) -> Expr<'a> {
+        Expr::Variable(variable)
+    }
+    /// Fold an assign expression
+    fn fold_assign(&mut self, assign: Assign<'a>) -> Expr<'a> {
+        fold_assign(self, assign)
+    }
+    /// Fold an expression which failed to parse, it has no children
+    fn fold_error_expr(&mut self, error: ErrorExpr) -> Expr<'a> {
+        Expr::Error(error)
+    }
+}
+
+pub fn fold_statement<'a, F: Folder<'a> + ?Sized>(
+    folder: &mut F,
+    statement: Statement<'a>,
+) -> Statement<'a> {
+    match statement {
+        Statement::Expression(v) => folder.fold_expression_statement(v),
+        Statement::Print(v) => folder.fold_print_statement(v),
+        Statement::Variable(v) => folder.fold_variable_statement(v),
+        Statement::Block(v) => folder.fold_block_statement(v),
+        Statement::Error(v) => folder.fold_error_statement(v),
+    }
+}
+
+pub fn fold_expression_statement<'a, F: Folder<'a> + ?Sized>(
+    folder: &mut F,
+    statement: ExpressionStatement<'a>,
+) -> Statement<'a> {
+    Statement::Expression(ExpressionStatement {
+        expr: folder.fold_expr(statement.expr),
+        ..statement
+    })
+}
+
+pub fn fold_print_statement<'a, F: Folder<'a> + ?Sized>(
+    folder: &mut F,
+    statement: PrintStatement<'a>,
+) -> Statement<'a> {
+    Statement::Print(PrintStatement {
+        expr: folder.fold_expr(statement.expr),
+        ..statement
+    })
+}
+
+pub fn fold_variable_statement<'a, F: Folder<'a> + ?Sized>(
+    folder: &mut F,
+    statement: VariableStatement<'a>,
+) -> Statement<'a> {
+    Statement::Variable(VariableStatement {
+        initializer: statement.initializer.map(|e| folder.fold_expr(e)),
+        ..statement
+    })
+}
+
+pub fn fold_block_statement<'a, F: Folder<'a> + ?Sized>(
+    folder: &mut F,
+    statement: BlockStatement<'a>,
+) -> Statement<'a> {
+    Statement::Block(BlockStatement {
+        statements: statement
+            .statements
+            .into_iter()
+            .map(|s| folder.fold_statement(s))
+            .collect(),
+        ..statement
+    })
+}
+
+pub fn fold_expr<'a, F: Folder<'a> + ?Sized>(folder: &mut F, expr: Expr<'a>) -> Expr<'a> {
+    match expr {
+        Expr::Unary(v) => folder.fold_unary(v),
+        Expr::Binary(v) => folder.fold_binary(v),
+        Expr::Grouping(v) => folder.fold_grouping(v),
+        Expr::Literal(v) => folder.fold_literal(v),
+        Expr::Variable(v) => folder.fold_variable(v),
+        Expr::Assign(v) => folder.fold_assign(v),
+        Expr::Error(v) => folder.fold_error_expr(v),
+    }
+}
+
+pub fn fold_unary<'a, F: Folder<'a> + ?Sized>(folder: &mut F, unary: Unary<'a>) -> Expr<'a> {
+    Expr::Unary(Unary {
+        expr: folder.arena().alloc(folder.fold_expr(unary.expr.clone())),
+        ..unary
+    })
+}
+
+pub fn fold_binary<'a, F: Folder<'a> + ?Sized>(folder: &mut F, binary: Binary<'a>) -> Expr<'a> {
+    Expr::Binary(Binary {
+        left: folder.arena().alloc(folder.fold_expr(binary.left.clone())),
+        right: folder.arena().alloc(folder.fold_expr(binary.right.clone())),
+        ..binary
+    })
+}
+
+pub fn fold_grouping<'a, F: Folder<'a> + ?Sized>(
+    folder: &mut F,
+    grouping: Grouping<'a>,
+) -> Expr<'a> {
+    Expr::Grouping(Grouping {
+        expr: folder
+            .arena()
+            .alloc(folder.fold_expr(grouping.expr.clone())),
+        ..grouping
+    })
+}
+
+pub fn fold_assign<'a, F: Folder<'a> + ?Sized>(folder: &mut F, assign: Assign<'a>) -> Expr<'a> {
+    Expr::Assign(Assign {
+        value: folder.arena().alloc(folder.fold_expr(assign.value.clone())),
+        ..assign
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::Folder;
+    use crate::{
+        ast::{expression::Grouping, visitor::ASTPrettyPrinter, Arena, Expr, Statement},
+        parser::Parser,
+        scanner::Scanner,
+    };
+
+    /// Replaces every grouping by the expression it contains
+    struct Ungroup<'a>(&'a Arena<'a>);
+
+    impl<'a> Folder<'a> for Ungroup<'a> {
+        fn arena(&self) -> &'a Arena<'a> {
+            self.0
+        }
+
+        fn fold_grouping(&mut self, grouping: Grouping<'a>) -> Expr<'a> {
+            self.fold_expr(grouping.expr.clone())
+        }
+    }
+
+    #[test]
+    fn test_rewrite() {
+        let tokens = Scanner::new("print ((1 + (2)) * 3); { a = (b); }")
+            .scan_tokens()
+            .unwrap();
+        let arena = Arena::new();
+        let statements: Vec<Statement> = Parser::new(tokens, &arena)
+            .parse()
+            .unwrap()
+            .into_iter()
+            .map(|s| Ungroup(&arena).fold_statement(s))
+            .collect();
+
+        assert_eq!(
+            ASTPrettyPrinter::new().print(&statements),
+            "print\n└─ *\n│  └─ +\n│  │  └─ 1\n│  │  └─ 2\n│  └─ 3\nblock\n└─ a =\n│  └─ b"
+        );
+    }
+}
@@ -1,10 +1,22 @@
-// mod ast_pretty_printer;
+mod ast_dot_printer;
+mod ast_pretty_printer;
+pub mod folder;
+pub mod walker;
 
-// pub use ast_pretty_printer::ASTPrettyPrinter;
+pub use ast_dot_printer::ASTDotPrinter;
+pub use ast_pretty_printer::ASTPrettyPrinter;
+pub use folder::Folder;
+pub use walker::Walker;
 
 use super::{
-    expression::{Assign, Binary, Grouping, Literal, Unary, Variable},
-    statement::{BlockStatement, ExpressionStatement, PrintStatement, VariableStatement},
+    expression::{Assign, Binary, ErrorExpr, Grouping, Literal, Unary, Variable},
+    statement::{
+        BlockStatement,
+        ErrorStatement,
+        ExpressionStatement,
+        PrintStatement,
+        VariableStatement,
+    },
 };
 
 /// Base trait to define a visitor for the AST
@@ -23,6 +35,8 @@ pub trait ExprVisitor {
     fn visit_variable(&mut self, variable: &Variable) -> Self::Return;
     /// Visit an assign expression
     fn visit_assign(&mut self, assign: &Assign) -> Self::Return;
+    /// Visit an expression which failed to parse
+    fn visit_error(&mut self, error: &ErrorExpr) -> Self::Return;
 }
 
 pub trait StatementVisitor {
@@ -36,4 +50,6 @@ pub trait StatementVisitor {
     fn visit_variable(&mut self, variable: &VariableStatement) -> Self::Return;
     /// visit a block statement
     fn visit_block(&mut self, block: &BlockStatement) -> Self::Return;
+    /// visit a statement which failed to parse
+    fn visit_error(&mut self, error: &ErrorStatement) -> Self::Return;
 }
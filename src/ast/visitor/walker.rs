@@ -0,0 +1,176 @@
+use crate::ast::{
+    expression::{Assign, Binary, ErrorExpr, Grouping, Literal, Unary, Variable},
+    statement::{
+        BlockStatement,
+        ErrorStatement,
+        ExpressionStatement,
+        PrintStatement,
+        VariableStatement,
+    },
+    Expr,
+    Statement,
+};
+
+/// Read-only traversal of the AST.
+///
+/// Every method defaults to recursing into the children of the visited node through the matching
+/// `walk_*` function, so an implementor only overrides the nodes it cares about. An overriding
+/// method can call the `walk_*` function itself to keep recursing.
+pub trait Walker {
+    /// Visit any statement, dispatching to the method of its variant
+    fn visit_statement(&mut self, statement: &Statement) {
+        walk_statement(self, statement)
+    }
+    /// Visit an expression statement
+    fn visit_expression_statement(&mut self, statement: &ExpressionStatement) {
+        walk_expression_statement(self, statement)
+    }
+    /// Visit a print statement
+    fn visit_print_statement(&mut self, statement: &PrintStatement) {
+        walk_print_statement(self, statement)
+    }
+    /// Visit a variable statement
+    fn visit_variable_statement(&mut self, statement: &VariableStatement) {
+        walk_variable_statement(self, statement)
+    }
+    /// Visit a block statement
+    fn visit_block_statement(&mut self, statement: &BlockStatement) {
+        walk_block_statement(self, statement)
+    }
+    /// Visit a statement which failed to parse, it has no children
+    fn visit_error_statement(&mut self, _statement: &ErrorStatement) {}
+
+    /// Visit any expression, dispatching to the method of its variant
+    fn visit_expr(&mut self, expr: &Expr) {
+        walk_expr(self, expr)
+    }
+    /// Visit an unary expression
+    fn visit_unary(&mut self, unary: &Unary) {
+        walk_unary(self, unary)
+    }
+    /// Visit a binary expression
+    fn visit_binary(&mut self, binary: &Binary) {
+        walk_binary(self, binary)
+    }
+    /// Visit a grouping expression
+    fn visit_grouping(&mut self, grouping: &Grouping) {
+        walk_grouping(self, grouping)
+    }
+    /// Visit a literal expression, it has no children
+    fn visit_literal(&mut self, _literal: &Literal) {}
+    /// Visit a variable expression, it has no children
+    fn visit_variable(&mut self, _variable: &Variable) {}
+    /// Visit an assign expression
+    fn visit_assign(&mut self, assign: &Assign) {
+        walk_assign(self, assign)
+    }
+    /// Visit an expression which failed to parse, it has no children
+    fn visit_error_expr(&mut self, _error: &ErrorExpr) {}
+}
+
+pub fn walk_statement<W: Walker + ?Sized>(walker: &mut W, statement: &Statement) {
+    match statement {
+        Statement::Expression(v) => walker.visit_expression_statement(v),
+        Statement::Print(v) => walker.visit_print_statement(v),
+        Statement::Variable(v) => walker.visit_variable_statement(v),
+        Statement::Block(v) => walker.visit_block_statement(v),
+        Statement::Error(v) => walker.visit_error_statement(v),
+    }
+}
+
+pub fn walk_expression_statement<W: Walker + ?Sized>(
+    walker: &mut W,
+    statement: &ExpressionStatement,
+) {
+    walker.visit_expr(&statement.expr)
+}
+
+pub fn walk_print_statement<W: Walker + ?Sized>(walker: &mut W, statement: &PrintStatement) {
+    walker.visit_expr(&statement.expr)
+}
+
+pub fn walk_variable_statement<W: Walker + ?Sized>(walker: &mut W, statement: &VariableStatement) {
+    if let Some(initializer) = statement.initializer.as_ref() {
+        walker.visit_expr(initializer)
+    }
+}
+
+pub fn walk_block_statement<W: Walker + ?Sized>(walker: &mut W, statement: &BlockStatement) {
+    for s in statement.statements.iter() {
+        walker.visit_statement(s)
+    }
+}
+
+pub fn walk_expr<W: Walker + ?Sized>(walker: &mut W, expr: &Expr) {
+    match expr {
+        Expr::Unary(v) => walker.visit_unary(v),
+        Expr::Binary(v) => walker.visit_binary(v),
+        Expr::Grouping(v) => walker.visit_grouping(v),
+        Expr::Literal(v) => walker.visit_literal(v),
+        Expr::Variable(v) => walker.visit_variable(v),
+        Expr::Assign(v) => walker.visit_assign(v),
+        Expr::Error(v) => walker.visit_error_expr(v),
+    }
+}
+
+pub fn walk_unary<W: Walker + ?Sized>(walker: &mut W, unary: &Unary) {
+    walker.visit_expr(unary.expr)
+}
+
+pub fn walk_binary<W: Walker + ?Sized>(walker: &mut W, binary: &Binary) {
+    walker.visit_expr(binary.left);
+    walker.visit_expr(binary.right)
+}
+
+pub fn walk_grouping<W: Walker + ?Sized>(walker: &mut W, grouping: &Grouping) {
+    walker.visit_expr(grouping.expr)
+}
+
+pub fn walk_assign<W: Walker + ?Sized>(walker: &mut W, assign: &Assign) {
+    walker.visit_expr(assign.value)
+}
+
+#[cfg(test)]
+mod test {
+    use super::Walker;
+    use crate::{
+        ast::{
+            expression::{Assign, Variable},
+            Arena,
+        },
+        parser::Parser,
+        scanner::Scanner,
+    };
+
+    /// Collects the names of every variable read or written, in traversal order
+    #[derive(Default)]
+    struct NameCollector {
+        names: Vec<String>,
+    }
+
+    impl Walker for NameCollector {
+        fn visit_variable(&mut self, variable: &Variable) {
+            self.names.push(variable.name.lexeme.to_string());
+        }
+
+        fn visit_assign(&mut self, assign: &Assign) {
+            self.names.push(assign.name.lexeme.to_string());
+            super::walk_assign(self, assign);
+        }
+    }
+
+    #[test]
+    fn test_default_traversal() {
+        let tokens = Scanner::new("var a = b; { print -(c + d); e = f; } g;")
+            .scan_tokens()
+            .unwrap();
+        let arena = Arena::new();
+        let statements = Parser::new(tokens, &arena).parse().unwrap();
+
+        let mut collector = NameCollector::default();
+        for statement in statements.iter() {
+            collector.visit_statement(statement);
+        }
+        assert_eq!(collector.names, ["b", "c", "d", "e", "f", "g"]);
+    }
+}
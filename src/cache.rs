@@ -0,0 +1,261 @@
+use std::{env, fs, io, path::PathBuf, process};
+
+use log::debug;
+use sha2::{Digest, Sha256};
+
+use crate::{
+    ast::{
+        binary::{BinaryReader, BinaryResult, BinaryWriter},
+        Arena,
+        NodeId,
+        NodeMap,
+        Statement,
+    },
+    interpreter::resolver::{Resolution, Resolver},
+    optimizer::OptimizationLevel,
+};
+
+/// First bytes of every cache file
+const MAGIC: &[u8] = b"ROXC";
+/// Version of rox writing the cache, programs cached by another version are never loaded
+const VERSION: &str = env!("CARGO_PKG_VERSION");
+/// Version of the content of cache files, to bump whenever the binary encoding of the AST, the
+/// optimizer or the resolver changes, as a cached program is the result of all three
+const FORMAT: usize = 1;
+
+/// Program ready to be executed: optimized, with its variables resolved
+#[derive(Debug, PartialEq)]
+pub struct CachedProgram<'a> {
+    pub statements: Vec<Statement<'a>>,
+    pub resolutions: NodeMap<Resolution>,
+}
+
+/// Directory of programs that were already scanned, parsed and resolved, stored in the format of
+/// [`BinaryWriter`].
+///
+/// Programs are keyed on a hash of their source, the optimization level, the rox version and the
+/// [`FORMAT`] of the cache, so editing a file or upgrading rox makes the cached program stale
+/// rather than wrong. The versions and key are written again at the start of the file, so that a
+/// stale or foreign file is rejected before decoding it, and the decoded program is checked before
+/// being run.
+pub struct Cache {
+    dir: PathBuf,
+}
+
+impl Cache {
+    pub fn new(dir: PathBuf) -> Self {
+        Cache { dir }
+    }
+
+    /// `$ROX_CACHE_DIR` if set, otherwise the `rox` directory of the user's cache directory
+    pub fn default_dir() -> PathBuf {
+        if let Some(dir) = env::var_os("ROX_CACHE_DIR") {
+            return PathBuf::from(dir);
+        }
+        let cache_home = env::var_os("XDG_CACHE_HOME")
+            .map(PathBuf::from)
+            .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".cache")))
+            .unwrap_or_else(env::temp_dir);
+        cache_home.join("rox")
+    }
+
+    /// Key of the cached program of the given source
+    pub fn key(source: &str, optimization_level: OptimizationLevel) -> String {
+        let mut hasher = Sha256::new();
+        hasher.update(VERSION);
+        hasher.update([0]);
+        hasher.update(FORMAT.to_le_bytes());
+        hasher.update(format!("{optimization_level:?}"));
+        hasher.update([0]);
+        hasher.update(source);
+        hasher
+            .finalize()
+            .iter()
+            .map(|byte| format!("{byte:02x}"))
+            .collect()
+    }
+
+    /// Load the program cached under `key`, if there is a valid one, allocating its expressions in
+    /// `arena`
+    pub fn load<'a>(&self, key: &str, arena: &'a Arena<'a>) -> Option<CachedProgram<'a>> {
+        let path = self.path(key);
+        let bytes = fs::read(&path).ok()?;
+        if !bytes.starts_with(MAGIC) {
+            debug!("ignoring cache file {} of unknown format", path.display());
+            return None;
+        }
+
+        let mut reader = BinaryReader::new(&bytes[MAGIC.len()..]);
+        match read_program(&mut reader, key, arena) {
+            Ok(Some(program)) if reader.is_at_end() => Some(program),
+            Ok(Some(_)) => {
+                debug!("ignoring cache file {} with trailing bytes", path.display());
+                None
+            }
+            Ok(None) => {
+                debug!("ignoring stale cache file {}", path.display());
+                None
+            }
+            Err(e) => {
+                debug!("ignoring unreadable cache file {}: {}", path.display(), e);
+                None
+            }
+        }
+    }
+
+    /// Store the program under `key`, replacing any previous one at once so that concurrent runs
+    /// never read a partially written file
+    pub fn store(&self, key: &str, program: &CachedProgram) -> io::Result<()> {
+        let mut writer = BinaryWriter::new();
+        writer.write_str(VERSION);
+        writer.write_usize(FORMAT);
+        writer.write_str(key);
+        writer.write_statements(&program.statements);
+        writer.write_usize(program.resolutions.len());
+        for (id, resolution) in program.resolutions.iter() {
+            writer.write_usize(id.0);
+            match resolution {
+                Resolution::Local { depth, slot } => {
+                    writer.write_u8(0);
+                    writer.write_usize(*depth);
+                    writer.write_usize(*slot);
+                }
+                Resolution::Global => writer.write_u8(1),
+                Resolution::Undefined => writer.write_u8(2),
+            }
+        }
+
+        let mut bytes = MAGIC.to_vec();
+        bytes.extend(writer.into_bytes());
+        fs::create_dir_all(&self.dir)?;
+        let temporary = self.dir.join(format!("{key}.{}.tmp", process::id()));
+        fs::write(&temporary, bytes)?;
+        fs::rename(&temporary, self.path(key))
+    }
+
+    fn path(&self, key: &str) -> PathBuf {
+        self.dir.join(format!("{key}.roxc"))
+    }
+}
+
+/// Read the program following the magic bytes, `None` if it was written by another version or
+/// under another key
+fn read_program<'a>(
+    reader: &mut BinaryReader,
+    key: &str,
+    arena: &'a Arena<'a>,
+) -> BinaryResult<Option<CachedProgram<'a>>> {
+    if &*reader.read_str()? != VERSION
+        || reader.read_usize()? != FORMAT
+        || &*reader.read_str()? != key
+    {
+        return Ok(None);
+    }
+    let statements = reader.read_statements(arena)?;
+
+    let mut resolutions = NodeMap::default();
+    for _ in 0..reader.read_usize()? {
+        let id = NodeId(reader.read_usize()?);
+        let resolution = match reader.read_u8()? {
+            0 => Resolution::Local {
+                depth: reader.read_usize()?,
+                slot: reader.read_usize()?,
+            },
+            1 => Resolution::Global,
+            2 => Resolution::Undefined,
+            tag => return Err(reader.error(&format!("invalid resolution tag {tag}"))),
+        };
+        resolutions.insert(id, resolution);
+    }
+
+    if let Err(id) = NodeId::claim(&statements) {
+        return Err(reader.error(&format!("duplicate node id {}", id.0)));
+    }
+    if !Resolver::is_valid(&statements, &resolutions) {
+        return Err(reader.error("resolutions don't match the program"));
+    }
+    Ok(Some(CachedProgram {
+        statements,
+        resolutions,
+    }))
+}
+
+#[cfg(test)]
+mod test {
+    use std::{env, fs, process};
+
+    use super::{Cache, CachedProgram};
+    use crate::{
+        ast::Arena,
+        interpreter::resolver::Resolver,
+        optimizer::OptimizationLevel,
+        parser::Parser,
+        scanner::Scanner,
+    };
+
+    fn program<'a>(source: &str, arena: &'a Arena<'a>) -> CachedProgram<'a> {
+        let tokens = Scanner::new(source).scan_tokens().unwrap();
+        let statements = Parser::new(tokens, arena).parse().unwrap();
+        let resolutions = Resolver::resolve(&statements);
+        CachedProgram {
+            statements,
+            resolutions,
+        }
+    }
+
+    fn cache(name: &str) -> Cache {
+        let dir = env::temp_dir().join(format!("rox_cache_{name}_{}", process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        Cache::new(dir)
+    }
+
+    #[test]
+    fn test_store_and_load() {
+        let cache = cache("store_and_load");
+        let source = "var a = \"x\"; { var b = a + 1; print -b; a = b; }";
+        let key = Cache::key(source, OptimizationLevel::O1);
+        let arena = Arena::new();
+        assert_eq!(cache.load(&key, &arena), None);
+
+        let program = program(source, &arena);
+        cache.store(&key, &program).unwrap();
+        assert_eq!(cache.load(&key, &arena), Some(program));
+    }
+
+    #[test]
+    fn test_key() {
+        let key = Cache::key("print 1;", OptimizationLevel::O1);
+        assert_eq!(key.len(), 64);
+        assert_eq!(key, Cache::key("print 1;", OptimizationLevel::O1));
+        assert_ne!(key, Cache::key("print 2;", OptimizationLevel::O1));
+        assert_ne!(key, Cache::key("print 1;", OptimizationLevel::O0));
+    }
+
+    #[test]
+    fn test_invalid_files_are_ignored() {
+        let cache = cache("invalid");
+        let key = Cache::key("print 1;", OptimizationLevel::O1);
+        let arena = Arena::new();
+        cache.store(&key, &program("print 1;", &arena)).unwrap();
+
+        // a file stored under another key, e.g. renamed by hand
+        let other = Cache::key("print 2;", OptimizationLevel::O1);
+        fs::copy(cache.path(&key), cache.path(&other)).unwrap();
+        assert_eq!(cache.load(&other, &arena), None);
+
+        fs::write(cache.path(&key), b"garbage").unwrap();
+        assert_eq!(cache.load(&key, &arena), None);
+    }
+
+    #[test]
+    fn test_programs_not_matching_their_resolutions_are_ignored() {
+        let cache = cache("mismatch");
+        let source = "{ var a = 1; print a; }";
+        let key = Cache::key(source, OptimizationLevel::O1);
+        let arena = Arena::new();
+        let mut program = program(source, &arena);
+        program.resolutions.clear();
+        cache.store(&key, &program).unwrap();
+        assert_eq!(cache.load(&key, &arena), None);
+    }
+}
@@ -0,0 +1,249 @@
+use std::{collections::HashSet, fmt::Display, fs, io, rc::Rc};
+
+use camino::{Utf8Path, Utf8PathBuf};
+use rayon::prelude::*;
+use thiserror::Error;
+
+use crate::{
+    ast::{
+        expression::{Assign, Variable},
+        statement::{ErrorStatement, VariableStatement},
+        visitor::walker::{self, Walker},
+        Arena,
+        NodeMap,
+    },
+    error::FacingRoxError,
+    incremental::ParsedSource,
+    interpreter::resolver::{Resolution, Resolver},
+    token::{Token, TokenType},
+};
+
+/// Problem found in a program without running it, which running it would run into
+#[derive(Error, Debug, PartialEq)]
+pub struct StaticError {
+    line_index: usize,
+    msg: String,
+}
+
+impl StaticError {
+    pub fn new(line_index: usize, msg: String) -> Self {
+        Self { line_index, msg }
+    }
+}
+
+impl Display for StaticError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Static Error - line {}: {}", self.line_index, self.msg)
+    }
+}
+
+/// Diagnostics of a checked file, in the order they were found
+pub struct Report {
+    pub path: Utf8PathBuf,
+    pub diagnostics: Vec<String>,
+}
+
+/// Check the given files and the `.rox` files of the given directories, recursively, in parallel.
+/// The reports are sorted by path.
+pub fn check_paths(paths: &[Utf8PathBuf]) -> Vec<Report> {
+    let mut files = Vec::new();
+    let mut reports = Vec::new();
+    for path in paths {
+        if let Err(e) = collect_files(path, &mut files) {
+            reports.push(Report {
+                path: path.clone(),
+                diagnostics: vec![e.to_string()],
+            });
+        }
+    }
+    files.sort_unstable();
+    files.dedup();
+
+    // ASTs can't be sent between threads, each file is checked and reported by a single one
+    reports.par_extend(files.into_par_iter().map(|path| {
+        let diagnostics = match fs::read_to_string(&path) {
+            Ok(source) => check_source(source)
+                .into_iter()
+                .map(|e| e.to_string())
+                .collect(),
+            Err(e) => vec![e.to_string()],
+        };
+        Report { path, diagnostics }
+    }));
+    reports.sort_by(|a, b| a.path.cmp(&b.path));
+    reports
+}
+
+/// `path` if it's a file, otherwise the `.rox` files under it
+fn collect_files(path: &Utf8Path, files: &mut Vec<Utf8PathBuf>) -> io::Result<()> {
+    if !fs::metadata(path)?.is_dir() {
+        files.push(path.to_path_buf());
+        return Ok(());
+    }
+    for entry in path.read_dir()? {
+        let path = Utf8PathBuf::try_from(entry?.path())
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        if path.is_dir() || path.extension() == Some("rox") {
+            collect_files(&path, files)?;
+        }
+    }
+    Ok(())
+}
+
+/// Scan, parse and analyze a program, reporting every error found
+pub fn check_source(source: String) -> Vec<FacingRoxError> {
+    let arena = Arena::new();
+    let parsed = ParsedSource::new(source, &arena);
+    let mut errors: Vec<FacingRoxError> = parsed
+        .scanner_errors()
+        .cloned()
+        .map(FacingRoxError::from)
+        .collect();
+    errors.extend(
+        parsed
+            .parser_errors()
+            .iter()
+            .cloned()
+            .map(FacingRoxError::from),
+    );
+
+    let resolutions = Resolver::resolve(parsed.statements());
+    let mut checker = UndefinedVariables {
+        resolutions: &resolutions,
+        tokens: parsed.tokens(),
+        globals: HashSet::new(),
+        declared_by_errors: HashSet::new(),
+        errors: Vec::new(),
+    };
+    for statement in parsed.statements() {
+        checker.visit_statement(statement);
+    }
+    errors.extend(checker.errors.into_iter().map(FacingRoxError::from));
+    errors
+}
+
+/// Finds the variables read or assigned before they're defined, which is always an error at
+/// runtime as nothing runs conditionally
+struct UndefinedVariables<'a> {
+    resolutions: &'a NodeMap<Resolution>,
+    tokens: &'a [Token],
+    /// Globals defined so far
+    globals: HashSet<Rc<str>>,
+    /// Names of the variables declared by statements which failed to parse, which aren't reported
+    /// as undefined since their parsing error already is
+    declared_by_errors: HashSet<Rc<str>>,
+    errors: Vec<StaticError>,
+}
+
+impl UndefinedVariables<'_> {
+    fn check(&mut self, resolution: Resolution, name: &Rc<str>, line_index: usize) {
+        if self.declared_by_errors.contains(name) {
+            return;
+        }
+        let defined = match resolution {
+            Resolution::Local { .. } => true,
+            Resolution::Global => self.globals.contains(name),
+            Resolution::Undefined => false,
+        };
+        if !defined {
+            self.errors.push(StaticError::new(
+                line_index,
+                format!("Undefined variable '{name}'"),
+            ));
+        }
+    }
+}
+
+impl Walker for UndefinedVariables<'_> {
+    fn visit_error_statement(&mut self, statement: &ErrorStatement) {
+        let start = self
+            .tokens
+            .partition_point(|token| token.span.start < statement.span.start);
+        let end = self
+            .tokens
+            .partition_point(|token| token.span.end <= statement.span.end);
+        for pair in self.tokens[start..end.max(start)].windows(2) {
+            if pair[0].token_type == TokenType::Var && pair[1].token_type == TokenType::Identifier {
+                self.declared_by_errors.insert(pair[1].lexeme.clone());
+            }
+        }
+    }
+
+    fn visit_variable_statement(&mut self, statement: &VariableStatement) {
+        walker::walk_variable_statement(self, statement);
+        if self.resolutions[&statement.id] == Resolution::Global {
+            self.globals.insert(statement.name.lexeme.clone());
+        }
+    }
+
+    fn visit_variable(&mut self, variable: &Variable) {
+        let resolution = self.resolutions[&variable.id];
+        self.check(resolution, &variable.name.lexeme, variable.name.line);
+    }
+
+    fn visit_assign(&mut self, assign: &Assign) {
+        walker::walk_assign(self, assign);
+        let resolution = self.resolutions[&assign.id];
+        self.check(resolution, &assign.name.lexeme, assign.name.line);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::check_source;
+
+    fn diagnostics(source: &str) -> Vec<String> {
+        check_source(source.to_string())
+            .into_iter()
+            .map(|e| e.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_valid_program() {
+        let source = "var a = 1; { var b = a; b = b + 1; { print a + b; } } a = 2;";
+        assert_eq!(diagnostics(source), Vec::<String>::new());
+    }
+
+    #[test]
+    fn test_every_error_is_reported() {
+        let source = "print (1;\nvar a = @;\nvar b = \"b\nprint b;";
+        assert_eq!(
+            diagnostics(source),
+            vec![
+                "Scanning Error - line 1: Unexpected character",
+                "Scanning Error - line 3: Unterminated string.",
+                "Parsing Error - line 0 at ;: Expect ')' after expression.",
+                "Parsing Error - line 1 at ;: Expected expression",
+                "Parsing Error - line 3 at end: Expected expression",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_undefined_variables() {
+        let source = "print a;\nvar a = a;\n{ var c = 1; { c = 2; print c; } }\nd = 1;\nvar d;";
+        assert_eq!(
+            diagnostics(source),
+            vec![
+                "Static Error - line 0: Undefined variable 'a'",
+                "Static Error - line 1: Undefined variable 'a'",
+                "Static Error - line 2: Undefined variable 'c'",
+                "Static Error - line 3: Undefined variable 'd'",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_statements_failing_to_parse_declare_nothing_undefined() {
+        let source = "var a = (1;\nprint a;\n{ var b = ; b = 2; }\nprint c;";
+        assert_eq!(
+            diagnostics(source),
+            vec![
+                "Parsing Error - line 0 at ;: Expect ')' after expression.",
+                "Parsing Error - line 2 at ;: Expected expression",
+                "Static Error - line 3: Undefined variable 'c'",
+            ]
+        );
+    }
+}
@@ -1,9 +1,12 @@
 use std::io;
 
 use crate::{
+    check::StaticError,
     interpreter::error::InterpreterError,
     parser::error::ParserError,
+    repl::command::CommandError,
     scanner::error::ScannerError,
+    vm::error::CompilerError,
 };
 
 #[allow(clippy::enum_variant_names)]
@@ -17,6 +20,21 @@ pub enum FacingRoxError {
     ScannerError(#[from] ScannerError),
     #[error(transparent)]
     InterpreterError(#[from] InterpreterError),
+    #[error(transparent)]
+    CompilerError(#[from] CompilerError),
+    #[error(transparent)]
+    StaticError(#[from] StaticError),
+    #[error("JSON Error - {0}")]
+    JsonError(#[from] serde_json::Error),
+    #[error(transparent)]
+    CommandError(#[from] CommandError),
+    #[error("Line Editing Error - {0}")]
+    LineEditingError(#[from] rustyline::error::ReadlineError),
+    #[error("Watch Error - {0}")]
+    WatchError(#[from] notify::Error),
+    /// Several errors found at once, e.g. every parsing error of a program, one per line
+    #[error("{}", .0.iter().map(ToString::to_string).collect::<Vec<_>>().join("\n"))]
+    Errors(Vec<FacingRoxError>),
 }
 
 pub type FacingRoxResult<T> = Result<T, FacingRoxError>;
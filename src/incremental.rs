@@ -0,0 +1,781 @@
+use std::{mem, ops::Range};
+
+use crate::{
+    ast::{
+        expression::{Assign, Binary, ErrorExpr, Grouping, Literal, Unary, Variable},
+        statement::{
+            BlockStatement,
+            ErrorStatement,
+            ExpressionStatement,
+            PrintStatement,
+            VariableStatement,
+        },
+        visitor::{folder, Folder},
+        Arena,
+        Expr,
+        NodeMap,
+        Statement,
+    },
+    parser::{error::ParserError, Parser},
+    scanner::{error::ScannerError, Scanner},
+    token::{Span, Token},
+};
+
+/// Replacement of the bytes `range` of a source by `replacement`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TextEdit {
+    pub range: Range<usize>,
+    pub replacement: String,
+}
+
+impl TextEdit {
+    pub fn new(range: Range<usize>, replacement: &str) -> Self {
+        Self {
+            range,
+            replacement: replacement.to_owned(),
+        }
+    }
+}
+
+/// Source kept scanned and parsed while it's being edited, e.g. by an editor.
+///
+/// An edit scans again from the end of the token before it, until the scanner gets to where an
+/// old token started after the edit: from there on the old tokens are kept. Then the smallest list
+/// of declarations containing the changed tokens, the body of a block or the whole program, is
+/// parsed again from the declaration before them until the parser gets to where an old
+/// declaration of the list started, falling back to the enclosing list when the changed tokens
+/// unbalance the braces. Everything after the edit is moved by its size.
+///
+/// The result is always the one of scanning and parsing the whole new source, except for the
+/// identities of the new nodes. Scanning errors don't prevent parsing, the tokens scanned around
+/// them are parsed.
+///
+/// The expressions are allocated in the given arena, including the ones parsed again or moved by
+/// an edit: the ones they replace are only freed with the arena.
+pub struct ParsedSource<'a> {
+    source: String,
+    tokens: Vec<Token>,
+    /// Scanning errors along with the byte offset of the lexeme they're about
+    scanner_errors: Vec<(usize, ScannerError)>,
+    statements: Vec<Statement<'a>>,
+    parser_errors: Vec<ParserError>,
+    /// Number of parsing errors of the declarations having some, see [`Parser::into_parts`]
+    error_counts: NodeMap<usize>,
+    arena: &'a Arena<'a>,
+}
+
+impl<'a> ParsedSource<'a> {
+    pub fn new(source: String, arena: &'a Arena<'a>) -> Self {
+        let mut scanner = Scanner::new(&source);
+        let mut tokens = Vec::new();
+        let mut scanner_errors = Vec::new();
+        while scan_lexeme(&mut scanner, &mut tokens, &mut scanner_errors) {}
+        tokens.push(scanner.eof());
+
+        let mut parser = Parser::new(tokens, arena);
+        let mut statements = Vec::new();
+        while !parser.at_end_of_declarations() {
+            statements.push(parser.next_declaration());
+        }
+        let (tokens, parser_errors, error_counts) = parser.into_parts();
+
+        Self {
+            source,
+            tokens,
+            scanner_errors,
+            statements,
+            parser_errors,
+            error_counts,
+            arena,
+        }
+    }
+
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    pub fn tokens(&self) -> &[Token] {
+        &self.tokens
+    }
+
+    pub fn scanner_errors(&self) -> impl Iterator<Item = &ScannerError> {
+        self.scanner_errors.iter().map(|(_, error)| error)
+    }
+
+    pub fn statements(&self) -> &[Statement<'a>] {
+        &self.statements
+    }
+
+    pub fn parser_errors(&self) -> &[ParserError] {
+        &self.parser_errors
+    }
+
+    /// Apply the edit to the source, then update the tokens and the AST.
+    ///
+    /// Panics if the range of the edit is out of the source or doesn't fall on character
+    /// boundaries.
+    pub fn edit(&mut self, edit: &TextEdit) {
+        let TextEdit { range, replacement } = edit;
+        self.source.replace_range(range.clone(), replacement);
+
+        // scanning a token may look at the character following it, a lexeme starts where the last
+        // token which doesn't look at the edit ends
+        let first = self
+            .tokens
+            .partition_point(|t| t.span.end + 1 < range.start);
+        let (restart, restart_line) = match first.checked_sub(1) {
+            Some(index) => (
+                self.tokens[index].span.end,
+                self.tokens[index].span.end_line,
+            ),
+            None => (0, 0),
+        };
+
+        // scan until a lexeme starts where an old token starts after the edit, the end of file
+        // being such a token
+        let bytes = replacement.len() as isize - range.len() as isize;
+        let mut scanner = Scanner::resume(&self.source, restart, restart_line);
+        let mut scanned = Vec::new();
+        let mut scanner_errors = Vec::new();
+        let mut reused = self.tokens.partition_point(|t| t.span.start < range.end);
+        let lines = loop {
+            let (offset, line) = scanner.position();
+            while self.tokens[reused].span.start.wrapping_add_signed(bytes) < offset {
+                reused += 1;
+            }
+            let token = &self.tokens[reused];
+            if token.span.start.wrapping_add_signed(bytes) == offset {
+                break line as isize - token.span.start_line as isize;
+            }
+            scan_lexeme(&mut scanner, &mut scanned, &mut scanner_errors);
+        };
+
+        let mut shift = Shift {
+            from: self.tokens[reused].span.start,
+            bytes,
+            lines,
+            arena: self.arena,
+        };
+        let rescanned = self
+            .scanner_errors
+            .partition_point(|(offset, _)| *offset < restart);
+        let moved = self
+            .scanner_errors
+            .partition_point(|(offset, _)| *offset < shift.from);
+        let moved: Vec<_> = self
+            .scanner_errors
+            .drain(moved..)
+            .map(|(offset, error)| (shift.offset(offset), shift.scanner_error(&error)))
+            .collect();
+        self.scanner_errors.truncate(rescanned);
+        self.scanner_errors.extend(scanner_errors);
+        self.scanner_errors.extend(moved);
+
+        for token in self.tokens[reused..].iter_mut() {
+            *token = shift.token(token);
+        }
+        for error in self.parser_errors.iter_mut() {
+            if error.token().span.end >= shift.from {
+                *error = ParserError::new(shift.token(error.token()), error.msg().to_owned());
+            }
+        }
+
+        if reused == first && scanned.is_empty() {
+            // the tokens are the same, the declarations after the edit only move
+            let moved = self
+                .statements
+                .partition_point(|s| s.span().end < shift.from);
+            shift_from(&mut self.statements, moved, &mut shift);
+            return;
+        }
+        self.tokens.splice(first..reused, scanned);
+        self.parse_again(restart, &mut shift);
+    }
+
+    /// Parse again the declarations of the tokens starting between the byte offset `restart` and
+    /// the one `shift` moves from, the AST being the one of the old source
+    fn parse_again(&mut self, restart: usize, shift: &mut Shift<'a>) {
+        // blocks whose braces surround the changed tokens, from the outermost
+        let mut path = Vec::new();
+        let mut statements = &self.statements;
+        loop {
+            let index = statements.partition_point(|s| s.span().end <= restart);
+            match statements.get(index) {
+                Some(Statement::Block(block))
+                    if block.span.start < restart
+                        && block.span.end > shift.from
+                        && is_closed(block) =>
+                {
+                    path.push(index);
+                    statements = &block.statements;
+                }
+                _ => break,
+            }
+        }
+
+        while !self.parse_declarations_again(&path, restart, shift) {
+            path.pop();
+        }
+    }
+
+    /// Parse again the declarations of the block at `path`, or of the program when it's empty,
+    /// unless the changed tokens close it or open a block which the old closing brace closes
+    fn parse_declarations_again(
+        &mut self,
+        path: &[usize],
+        restart: usize,
+        shift: &mut Shift<'a>,
+    ) -> bool {
+        let statements = declarations(&self.statements, path);
+        let (start, end) = match path.split_last() {
+            Some((last, parents)) => {
+                let Statement::Block(block) = &declarations(&self.statements, parents)[*last]
+                else {
+                    unreachable!("only blocks have declarations");
+                };
+                let right_brace = block.span.end - "}".len();
+                (
+                    self.token_index(block.span.start) + 1,
+                    Some(shift.offset(right_brace)),
+                )
+            }
+            None => (0, None),
+        };
+
+        // from the declaration before the changed tokens, which may end differently
+        let replaced_start = statements
+            .partition_point(|s| s.span().start < restart)
+            .saturating_sub(1);
+        let start = match statements.get(replaced_start) {
+            Some(statement) if statement.span().start < restart => {
+                self.token_index(statement.span().start)
+            }
+            _ => start,
+        };
+
+        // until a declaration starts where an old one, starting after the changed tokens, did
+        let mut parser = Parser::resume(mem::take(&mut self.tokens), self.arena, start, path.len());
+        let mut parsed = Vec::new();
+        let mut kept = statements.partition_point(|s| s.span().start < shift.from);
+        let replaced_end = loop {
+            let position = parser.current().span.start;
+            while kept < statements.len() && shift.offset(statements[kept].span().start) < position
+            {
+                kept += 1;
+            }
+            if kept < statements.len() && shift.offset(statements[kept].span().start) == position {
+                break Some(kept);
+            }
+            if parser.at_end_of_declarations() {
+                break match end {
+                    Some(end) if end != position => None,
+                    _ => Some(statements.len()),
+                };
+            }
+            if end.map_or(false, |end| position > end) {
+                break None;
+            }
+            parsed.push(parser.next_declaration());
+        };
+        let (tokens, errors, error_counts) = parser.into_parts();
+        self.tokens = tokens;
+        let Some(replaced_end) = replaced_end else {
+            return false;
+        };
+
+        let errors_before = self.errors_before(path, replaced_start);
+        let replaced_errors: usize = statements[replaced_start..replaced_end]
+            .iter()
+            .map(|s| self.error_count(s))
+            .sum();
+        let parsed_errors = errors.len();
+        self.parser_errors
+            .splice(errors_before..errors_before + replaced_errors, errors);
+
+        // the enclosing blocks have as many errors more or less
+        let mut statements = &self.statements;
+        for index in path {
+            let id = statements[*index].id();
+            let count = self.error_count(&statements[*index]) + parsed_errors - replaced_errors;
+            if count > 0 {
+                self.error_counts.insert(id, count);
+            } else {
+                self.error_counts.remove(&id);
+            }
+            let Statement::Block(block) = &statements[*index] else {
+                unreachable!("only blocks have declarations");
+            };
+            statements = &block.statements;
+        }
+
+        let parsed_end = replaced_start + parsed.len();
+        let statements = declarations_mut(&mut self.statements, path);
+        for statement in statements.splice(replaced_start..replaced_end, parsed) {
+            forget_error_counts(&mut self.error_counts, &statement);
+        }
+        self.error_counts.extend(error_counts);
+
+        // move what follows, from the innermost block
+        shift_from(statements, parsed_end, shift);
+        for (depth, index) in path.iter().enumerate().rev() {
+            let statements = declarations_mut(&mut self.statements, &path[..depth]);
+            if let Statement::Block(block) = &mut statements[*index] {
+                block.span = shift.span(block.span);
+            }
+            shift_from(statements, index + 1, shift);
+        }
+        true
+    }
+
+    /// Index of the token starting at the given byte offset
+    fn token_index(&self, offset: usize) -> usize {
+        self.tokens.partition_point(|t| t.span.start < offset)
+    }
+
+    fn error_count(&self, statement: &Statement) -> usize {
+        self.error_counts.get(&statement.id()).copied().unwrap_or(0)
+    }
+
+    /// Number of parsing errors before the ones of the `index`th declaration of the block at
+    /// `path`: errors are in the order of the source, a block reporting its own one after the
+    /// ones of its declarations
+    fn errors_before(&self, path: &[usize], index: usize) -> usize {
+        let mut errors = 0;
+        let mut statements = &self.statements;
+        for index in path {
+            errors += statements[..*index]
+                .iter()
+                .map(|s| self.error_count(s))
+                .sum::<usize>();
+            let Statement::Block(block) = &statements[*index] else {
+                unreachable!("only blocks have declarations");
+            };
+            statements = &block.statements;
+        }
+        errors
+            + statements[..index]
+                .iter()
+                .map(|s| self.error_count(s))
+                .sum::<usize>()
+    }
+}
+
+/// Scan the next lexeme, returns whether there was one
+fn scan_lexeme(
+    scanner: &mut Scanner,
+    tokens: &mut Vec<Token>,
+    errors: &mut Vec<(usize, ScannerError)>,
+) -> bool {
+    let (offset, _) = scanner.position();
+    match scanner.scan_lexeme() {
+        Some(Ok(Some(token))) => tokens.push(token),
+        Some(Ok(None)) => (),
+        Some(Err(error)) => errors.push((offset, error)),
+        None => return false,
+    }
+    true
+}
+
+/// Whether the block ends with its closing brace, rather than with the source
+fn is_closed(block: &BlockStatement) -> bool {
+    match block.statements.last() {
+        Some(last) => last.span().end < block.span.end,
+        None => block.span.end - block.span.start > "{".len(),
+    }
+}
+
+/// Declarations of the block at `path`, each index being the one of a block in the declarations of
+/// the previous one, starting from the program
+fn declarations<'a, 'b>(
+    statements: &'b Vec<Statement<'a>>,
+    path: &[usize],
+) -> &'b Vec<Statement<'a>> {
+    path.iter()
+        .fold(statements, |statements, index| match &statements[*index] {
+            Statement::Block(block) => &block.statements,
+            _ => unreachable!("only blocks have declarations"),
+        })
+}
+
+fn declarations_mut<'a, 'b>(
+    statements: &'b mut Vec<Statement<'a>>,
+    path: &[usize],
+) -> &'b mut Vec<Statement<'a>> {
+    path.iter().fold(statements, |statements, index| {
+        match &mut statements[*index] {
+            Statement::Block(block) => &mut block.statements,
+            _ => unreachable!("only blocks have declarations"),
+        }
+    })
+}
+
+/// Move the declarations from the `index`th one
+fn shift_from<'a>(statements: &mut Vec<Statement<'a>>, index: usize, shift: &mut Shift<'a>) {
+    let moved: Vec<_> = statements
+        .drain(index..)
+        .map(|s| shift.fold_statement(s))
+        .collect();
+    statements.extend(moved);
+}
+
+fn forget_error_counts(error_counts: &mut NodeMap<usize>, statement: &Statement) {
+    error_counts.remove(&statement.id());
+    if let Statement::Block(block) = statement {
+        for statement in block.statements.iter() {
+            forget_error_counts(error_counts, statement);
+        }
+    }
+}
+
+/// Moves what is at or after the byte offset `from` by `bytes` bytes and `lines` lines
+struct Shift<'a> {
+    from: usize,
+    bytes: isize,
+    lines: isize,
+    /// Where the moved expressions are allocated
+    arena: &'a Arena<'a>,
+}
+
+impl Shift<'_> {
+    fn offset(&self, offset: usize) -> usize {
+        if offset >= self.from {
+            offset.wrapping_add_signed(self.bytes)
+        } else {
+            offset
+        }
+    }
+
+    fn line(&self, offset: usize, line: usize) -> usize {
+        if offset >= self.from {
+            line.wrapping_add_signed(self.lines)
+        } else {
+            line
+        }
+    }
+
+    fn span(&self, span: Span) -> Span {
+        Span::new(
+            self.offset(span.start),
+            self.offset(span.end),
+            self.line(span.start, span.start_line),
+            self.line(span.end, span.end_line),
+        )
+    }
+
+    fn token(&self, token: &Token) -> Token {
+        Token::new(
+            token.token_type,
+            token.lexeme.clone(),
+            self.line(token.span.end, token.line),
+            self.span(token.span),
+        )
+    }
+
+    fn scanner_error(&self, error: &ScannerError) -> ScannerError {
+        ScannerError::new(
+            error.line_index().wrapping_add_signed(self.lines),
+            error.msg().to_owned(),
+        )
+    }
+}
+
+impl<'a> Folder<'a> for Shift<'a> {
+    fn arena(&self) -> &'a Arena<'a> {
+        self.arena
+    }
+
+    fn fold_expression_statement(&mut self, statement: ExpressionStatement<'a>) -> Statement<'a> {
+        let span = self.span(statement.span);
+        folder::fold_expression_statement(self, ExpressionStatement { span, ..statement })
+    }
+
+    fn fold_print_statement(&mut self, statement: PrintStatement<'a>) -> Statement<'a> {
+        let span = self.span(statement.span);
+        folder::fold_print_statement(self, PrintStatement { span, ..statement })
+    }
+
+    fn fold_variable_statement(&mut self, statement: VariableStatement<'a>) -> Statement<'a> {
+        let span = self.span(statement.span);
+        let name = self.token(&statement.name);
+        folder::fold_variable_statement(
+            self,
+            VariableStatement {
+                span,
+                name,
+                ..statement
+            },
+        )
+    }
+
+    fn fold_block_statement(&mut self, statement: BlockStatement<'a>) -> Statement<'a> {
+        let span = self.span(statement.span);
+        folder::fold_block_statement(self, BlockStatement { span, ..statement })
+    }
+
+    fn fold_error_statement(&mut self, statement: ErrorStatement) -> Statement<'a> {
+        let span = self.span(statement.span);
+        Statement::Error(ErrorStatement { span, ..statement })
+    }
+
+    fn fold_unary(&mut self, unary: Unary<'a>) -> Expr<'a> {
+        let span = self.span(unary.span);
+        let op = self.token(&unary.op);
+        folder::fold_unary(self, Unary { span, op, ..unary })
+    }
+
+    fn fold_binary(&mut self, binary: Binary<'a>) -> Expr<'a> {
+        let span = self.span(binary.span);
+        let op = self.token(&binary.op);
+        folder::fold_binary(self, Binary { span, op, ..binary })
+    }
+
+    fn fold_grouping(&mut self, grouping: Grouping<'a>) -> Expr<'a> {
+        let span = self.span(grouping.span);
+        folder::fold_grouping(self, Grouping { span, ..grouping })
+    }
+
+    fn fold_literal(&mut self, literal: Literal) -> Expr<'a> {
+        let span = self.span(literal.span);
+        Expr::Literal(Literal { span, ..literal })
+    }
+
+    fn fold_variable(&mut self, variable: Variable) -> Expr<'a> {
+        let span = self.span(variable.span);
+        let name = self.token(&variable.name);
+        Expr::Variable(Variable {
+            span,
+            name,
+            ..variable
+        })
+    }
+
+    fn fold_assign(&mut self, assign: Assign<'a>) -> Expr<'a> {
+        let span = self.span(assign.span);
+        let name = self.token(&assign.name);
+        folder::fold_assign(
+            self,
+            Assign {
+                span,
+                name,
+                ..assign
+            },
+        )
+    }
+
+    fn fold_error_expr(&mut self, error: ErrorExpr) -> Expr<'a> {
+        let span = self.span(error.span);
+        Expr::Error(ErrorExpr { span, ..error })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use serde_json::Value;
+
+    use super::{ParsedSource, TextEdit};
+    use crate::{
+        ast::{Arena, NodeId, Statement},
+        parser::Parser,
+        scanner::Scanner,
+    };
+
+    /// JSON of the statements without the identities of their nodes
+    fn without_ids(statements: &[Statement]) -> Value {
+        fn strip(value: &mut Value) {
+            match value {
+                Value::Object(object) => {
+                    object.remove("id");
+                    object.values_mut().for_each(strip);
+                }
+                Value::Array(array) => array.iter_mut().for_each(strip),
+                _ => (),
+            }
+        }
+        let mut value = serde_json::to_value(statements).unwrap();
+        strip(&mut value);
+        value
+    }
+
+    fn assert_parsed_again(parsed: &ParsedSource) {
+        let arena = Arena::new();
+        let full = ParsedSource::new(parsed.source().to_owned(), &arena);
+        assert_eq!(parsed.tokens(), full.tokens(), "{:?}", parsed.source());
+        assert_eq!(
+            parsed.scanner_errors().collect::<Vec<_>>(),
+            full.scanner_errors().collect::<Vec<_>>(),
+            "{:?}",
+            parsed.source()
+        );
+        assert_eq!(
+            without_ids(parsed.statements()),
+            without_ids(full.statements()),
+            "{:?}",
+            parsed.source()
+        );
+        assert_eq!(
+            parsed.parser_errors(),
+            full.parser_errors(),
+            "{:?}",
+            parsed.source()
+        );
+    }
+
+    /// Apply the edits one after the other, checking each result against a full reparse
+    fn edit(source: &str, edits: &[(usize, usize, &str)]) {
+        let arena = Arena::new();
+        let mut parsed = ParsedSource::new(source.to_owned(), &arena);
+        for (start, end, replacement) in edits {
+            parsed.edit(&TextEdit::new(*start..*end, replacement));
+            assert_parsed_again(&parsed);
+        }
+    }
+
+    fn ids(statements: &[Statement]) -> Vec<NodeId> {
+        statements.iter().map(|s| s.id()).collect()
+    }
+
+    #[test]
+    fn test_new() {
+        let source = "var a = 1;\n{ print -a; a = (a + 2) * 3; }";
+        let arena = Arena::new();
+        let parsed = ParsedSource::new(source.to_owned(), &arena);
+        let tokens = Scanner::new(source).scan_tokens().unwrap();
+        let statements = Parser::new(tokens.clone(), &arena).parse().unwrap();
+        assert_eq!(parsed.tokens(), tokens);
+        assert_eq!(without_ids(parsed.statements()), without_ids(&statements));
+        assert!(parsed.parser_errors().is_empty());
+        assert_eq!(parsed.scanner_errors().count(), 0);
+    }
+
+    #[test]
+    fn test_declarations_outside_of_the_block_are_kept() {
+        let source = "var a = 1;\n{\n  print a;\n  var b = 2;\n  print b;\n}\nprint a;\n";
+        let arena = Arena::new();
+        let mut parsed = ParsedSource::new(source.to_owned(), &arena);
+        let before = ids(parsed.statements());
+        let Statement::Block(block) = &parsed.statements()[1] else {
+            unreachable!()
+        };
+        let block_before = ids(&block.statements);
+
+        let two = source.find('2').unwrap();
+        parsed.edit(&TextEdit::new(two..two + 1, "(3 +\n 4)"));
+        assert_parsed_again(&parsed);
+
+        assert_eq!(ids(parsed.statements()), before);
+        let Statement::Block(block) = &parsed.statements()[1] else {
+            unreachable!()
+        };
+        let block_after = ids(&block.statements);
+        assert_eq!(block_after[0], block_before[0]);
+        assert_ne!(block_after[1], block_before[1]);
+        assert_eq!(block_after[2], block_before[2]);
+        assert_eq!(block.statements[2].span().start_line, 5);
+    }
+
+    #[test]
+    fn test_tokens() {
+        // merging and splitting tokens
+        edit("a b;", &[(1, 2, ""), (1, 1, " "), (0, 1, "print")]);
+        // a number looks at the characters after its end
+        edit("print 1.a;", &[(8, 9, "5"), (8, 9, "b")]);
+        // comments and strings reaching further
+        edit(
+            "print 1;\nprint 2;\nprint 3;",
+            &[
+                (0, 0, "//"),
+                (0, 2, ""),
+                (9, 9, "\""),
+                (9, 10, ""),
+                (16, 16, "\""),
+            ],
+        );
+        // errors move with the lines
+        edit(
+            "@ print 1;\n# print 2;\n",
+            &[(0, 0, "\n\n"), (2, 3, ""), (0, 2, "")],
+        );
+        // the end of the source
+        edit("print 1;", &[(8, 8, " print"), (0, 14, ""), (0, 0, "{")]);
+    }
+
+    #[test]
+    fn test_declarations() {
+        // a declaration ends differently
+        edit("print 1\nprint 2;", &[(7, 7, ";"), (7, 8, "+")]);
+        // grouping recovery stops where the statement would
+        edit("x = (1 2 var y = 3;", &[(18, 18, ")"), (18, 19, "")]);
+        // braces opened or closed in a block
+        edit(
+            "{ print 1; { print 2; } print 3; }\nprint 4;",
+            &[(11, 12, ""), (11, 11, "{"), (22, 22, "}"), (0, 1, "")],
+        );
+        // an error in a block
+        edit("{ { print 1; } print 2; }", &[(9, 10, ""), (9, 9, "1")]);
+    }
+
+    #[test]
+    fn test_random_edits() {
+        const PIECES: &[&str] = &[
+            "{",
+            "}",
+            "(",
+            ")",
+            ";",
+            "print",
+            "var",
+            "x",
+            "=",
+            "+",
+            "-",
+            "1",
+            ".5",
+            "\"",
+            "//",
+            " ",
+            "\n",
+            "@",
+            "\u{e9}",
+            "print x;",
+            "var x = 1;",
+            "{ x = 2; }",
+        ];
+        // xorshift, so that runs are reproducible
+        let mut state: u64 = 0x2545_f491_4f6c_dd1d;
+        let mut random = move |bound: usize| {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            (state % bound as u64) as usize
+        };
+
+        // starting over now and then, so that edits land in nested blocks
+        let program = "var x = 1;\n{\n  print x;\n  {\n    x = x + 1;\n    { print (x); }\n  \
+                       }\n}\nprint x;\n";
+        let arena = Arena::new();
+        let mut parsed = ParsedSource::new(program.into(), &arena);
+        for step in 0..3000 {
+            if step % 50 == 0 {
+                parsed = ParsedSource::new(program.into(), &arena);
+            }
+            let source = parsed.source();
+            let boundaries: Vec<usize> = source
+                .char_indices()
+                .map(|(index, _)| index)
+                .chain([source.len()])
+                .collect();
+            let start = boundaries[random(boundaries.len())];
+            let end = if random(3) == 0 {
+                *boundaries
+                    .iter()
+                    .find(|b| **b >= start + random(8))
+                    .unwrap_or(&source.len())
+            } else {
+                start
+            };
+            let replacement = PIECES[random(PIECES.len())];
+            parsed.edit(&TextEdit::new(start..end, replacement));
+            assert_parsed_again(&parsed);
+        }
+    }
+}
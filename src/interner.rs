@@ -0,0 +1,46 @@
+use std::{cell::RefCell, collections::HashSet, rc::Rc};
+
+use serde::{Deserialize, Deserializer};
+
+thread_local! {
+    /// Strings interned so far, they live as long as the thread
+    static INTERNER: RefCell<HashSet<Rc<str>>> = RefCell::new(HashSet::new());
+}
+
+/// Shared copy of the given string, every call with the same content returns the same allocation.
+///
+/// Comparing two `Rc<str>` first compares their pointers, so interned strings are compared without
+/// looking at their content.
+pub fn intern(s: &str) -> Rc<str> {
+    INTERNER.with(|interner| {
+        let mut interner = interner.borrow_mut();
+        if let Some(interned) = interner.get(s) {
+            return interned.clone();
+        }
+        let interned: Rc<str> = Rc::from(s);
+        interner.insert(interned.clone());
+        interned
+    })
+}
+
+/// Deserialize an interned string, for use with `#[serde(deserialize_with = "...")]`
+pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Rc<str>, D::Error> {
+    let s = String::deserialize(deserializer)?;
+    Ok(intern(&s))
+}
+
+#[cfg(test)]
+mod test {
+    use std::rc::Rc;
+
+    use super::intern;
+
+    #[test]
+    fn test_intern() {
+        let a = intern("hello");
+        let b = intern(&String::from("hello"));
+        assert!(Rc::ptr_eq(&a, &b));
+        assert!(!Rc::ptr_eq(&a, &intern("world")));
+        assert_eq!(&*a, "hello");
+    }
+}
@@ -1,61 +1,124 @@
-use std::collections::HashMap;
+use std::{collections::HashMap, fmt, rc::Rc};
 
 use super::{
     error::{InterpreterError, InterpreterResult},
+    value::Value,
     EvaluatedExpr,
 };
 use crate::token::Token;
 
+/// Storage of the variables of a running program.
+///
+/// Globals are looked up by name. Locals are resolved ahead of time to a slot in the block they're
+/// declared in, the variables of every open block live next to each other in `locals` and `frames`
+/// holds the offset the variables of each block start at.
 #[derive(Default)]
 pub struct Environment {
-    values: HashMap<String, EvaluatedExpr>,
-    enclosing: Option<Box<Environment>>,
+    globals: HashMap<Rc<str>, Value>,
+    locals: Vec<Value>,
+    frames: Vec<usize>,
 }
 
 impl Environment {
-    pub fn set_enclosing(&mut self, enclosing: Environment) {
-        if self.enclosing.is_some() {
-            panic!("Cannot set enclosing envirnoment out as it's already set")
-        }
-        self.enclosing = Some(Box::new(enclosing))
+    /// Open the frame of a block, its variables are dropped by the matching `pop_frame`
+    pub fn push_frame(&mut self) {
+        self.frames.push(self.locals.len());
     }
 
-    pub fn take_enclosing(&mut self) -> Environment {
-        let ret = self.enclosing.take();
-        if let Some(b) = ret {
-            *b
-        } else {
-            panic!("Cannot take enclosing envirnoment out as it's not set")
-        }
+    pub fn pop_frame(&mut self) {
+        let start = self
+            .frames
+            .pop()
+            .expect("Cannot pop a frame as none is open");
+        self.locals.truncate(start);
     }
 
-    pub fn define(&mut self, name: String, value: EvaluatedExpr) {
-        self.values.insert(name, value);
+    pub fn define_global(&mut self, name: Rc<str>, value: EvaluatedExpr) {
+        self.globals.insert(name, value.into());
     }
 
-    pub fn get(&self, name: &Token) -> InterpreterResult<EvaluatedExpr> {
-        if self.values.contains_key(&name.lexeme) {
-            Ok(self.values.get(&name.lexeme).cloned().unwrap())
-        } else {
-            if let Some(enclosing) = self.enclosing.as_ref() {
-                return enclosing.get(name);
+    pub fn get_global(&self, name: &Token) -> InterpreterResult<EvaluatedExpr> {
+        self.globals
+            .get(&*name.lexeme)
+            .map(Value::to_evaluated)
+            .ok_or_else(|| {
+                InterpreterError::RuntimeError(format!("Undefined variable {}", name.lexeme))
+            })
+    }
+
+    pub fn assign_global(&mut self, name: &Token, value: EvaluatedExpr) -> InterpreterResult<()> {
+        match self.globals.get_mut(&*name.lexeme) {
+            Some(variable) => {
+                *variable = value.into();
+                Ok(())
             }
-            Err(InterpreterError::RuntimeError(format!(
-                "Undefined variable {}",
+            None => Err(InterpreterError::RuntimeError(format!(
+                "Undefined variable '{}'",
                 name.lexeme
-            )))
+            ))),
         }
     }
 
-    pub fn assign(&mut self, name: &Token, value: EvaluatedExpr) -> InterpreterResult<()> {
-        if self.values.contains_key(&name.lexeme) {
-            self.values.insert(name.lexeme.clone(), value);
-            Ok(())
+    /// Names of the globals, locals being only known by their slot
+    pub fn global_names(&self) -> impl Iterator<Item = &Rc<str>> {
+        self.globals.keys()
+    }
+
+    /// Define a variable of the innermost block, `slot` is either the slot of a variable it
+    /// redefines or the next free one
+    pub fn define_local(&mut self, slot: usize, value: EvaluatedExpr) {
+        let index = self.index(0, slot);
+        if index == self.locals.len() {
+            self.locals.push(value.into());
         } else {
-            Err(InterpreterError::RuntimeError(format!(
-                "Undefined variable '{}'",
-                name.lexeme
-            )))
+            self.locals[index] = value.into();
         }
     }
+
+    pub fn get_local(&self, depth: usize, slot: usize) -> EvaluatedExpr {
+        self.locals[self.index(depth, slot)].to_evaluated()
+    }
+
+    pub fn assign_local(&mut self, depth: usize, slot: usize, value: EvaluatedExpr) {
+        let index = self.index(depth, slot);
+        self.locals[index] = value.into();
+    }
+
+    /// Index in `locals` of the `slot`th variable of the block `depth` blocks above the innermost
+    fn index(&self, depth: usize, slot: usize) -> usize {
+        self.frames[self.frames.len() - 1 - depth] + slot
+    }
+}
+
+impl fmt::Display for Environment {
+    /// One variable per line: the globals, then the locals of each open block by slot, indented by
+    /// their depth
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt_globals(&self.globals, f)?;
+        for (depth, start) in self.frames.iter().enumerate() {
+            let end = self
+                .frames
+                .get(depth + 1)
+                .copied()
+                .unwrap_or(self.locals.len());
+            for (slot, value) in self.locals[*start..end].iter().enumerate() {
+                let indent = "  ".repeat(depth + 1);
+                writeln!(f, "{indent}#{slot} = {}", value.to_evaluated().repr())?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Write the globals sorted by name, one per line
+pub(crate) fn fmt_globals(
+    globals: &HashMap<Rc<str>, Value>,
+    f: &mut fmt::Formatter<'_>,
+) -> fmt::Result {
+    let mut globals: Vec<_> = globals.iter().collect();
+    globals.sort_unstable_by(|(a, _), (b, _)| a.cmp(b));
+    for (name, value) in globals {
+        writeln!(f, "{name} = {}", value.to_evaluated().repr())?;
+    }
+    Ok(())
 }
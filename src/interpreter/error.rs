@@ -6,6 +6,9 @@ pub enum InterpreterError {
     TypeError(String),
     #[error("RuntimeError: {0}")]
     RuntimeError(String),
+    /// The program holds nodes which failed to parse
+    #[error("SyntaxError: {0}")]
+    SyntaxError(String),
 }
 
 pub type InterpreterResult<T> = Result<T, InterpreterError>;
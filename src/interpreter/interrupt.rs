@@ -0,0 +1,140 @@
+use std::{
+    ptr,
+    sync::{
+        atomic::{AtomicBool, AtomicPtr, Ordering},
+        Arc,
+    },
+};
+
+use super::error::{InterpreterError, InterpreterResult};
+
+/// Request to stop a running program, shared by the interpreter or the VM running it, which
+/// [`Interrupt::check`] it as they run, with whatever asks it to stop
+#[derive(Debug, Default, Clone)]
+pub struct Interrupt(Arc<AtomicBool>);
+
+impl Interrupt {
+    /// Ask the running program to stop at its next check
+    pub fn request(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    /// Fail if the running program was asked to stop, clearing the request
+    pub fn check(&self) -> InterpreterResult<()> {
+        // a plain load is enough most of the time, the swap only happens once stopping
+        if self.0.load(Ordering::Relaxed) && self.0.swap(false, Ordering::Relaxed) {
+            return Err(InterpreterError::RuntimeError("Interrupted".to_string()));
+        }
+        Ok(())
+    }
+}
+
+/// Flag of the [`Interrupt`] of the current [`Catcher`], null when there is none
+static CAUGHT: AtomicPtr<AtomicBool> = AtomicPtr::new(ptr::null_mut());
+
+/// Turns SIGINT into a request of an [`Interrupt`] until it's dropped, when the previous handling
+/// of the signal is restored. The program then fails with an "Interrupted" runtime error instead
+/// of the process being killed, so the REPL keeps the variables defined so far.
+pub struct Catcher {
+    /// Keeps the flag the signal handler points to alive
+    interrupt: Interrupt,
+    #[cfg(unix)]
+    previous: Option<nix::sys::signal::SigAction>,
+}
+
+impl Catcher {
+    pub fn new(interrupt: &Interrupt) -> Self {
+        let interrupt = interrupt.clone();
+        // an interruption requested while nothing was running is dropped
+        interrupt.0.store(false, Ordering::Relaxed);
+        CAUGHT.store(Arc::as_ptr(&interrupt.0).cast_mut(), Ordering::SeqCst);
+        #[cfg(unix)]
+        {
+            use nix::sys::signal::{sigaction, SaFlags, SigAction, SigHandler, SigSet, Signal};
+
+            extern "C" fn handle_sigint(_: std::os::raw::c_int) {
+                let flag = CAUGHT.load(Ordering::SeqCst);
+                // SAFETY: the flag is kept alive by the catcher until it's no longer caught
+                if let Some(flag) = unsafe { flag.as_ref() } {
+                    flag.store(true, Ordering::Relaxed);
+                }
+            }
+
+            let action = SigAction::new(
+                SigHandler::Handler(handle_sigint),
+                SaFlags::SA_RESTART,
+                SigSet::empty(),
+            );
+            // SAFETY: the handler only loads and stores atomics, which is async-signal-safe
+            let previous = unsafe { sigaction(Signal::SIGINT, &action) }.ok();
+            Self {
+                interrupt,
+                previous,
+            }
+        }
+        #[cfg(not(unix))]
+        Self { interrupt }
+    }
+}
+
+impl Drop for Catcher {
+    fn drop(&mut self) {
+        #[cfg(unix)]
+        if let Some(previous) = self.previous.as_ref() {
+            // SAFETY: restores the handling the signal had before
+            let _ =
+                unsafe { nix::sys::signal::sigaction(nix::sys::signal::Signal::SIGINT, previous) };
+        }
+        let _ = CAUGHT.compare_exchange(
+            Arc::as_ptr(&self.interrupt.0).cast_mut(),
+            ptr::null_mut(),
+            Ordering::SeqCst,
+            Ordering::SeqCst,
+        );
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        ast::Arena,
+        interpreter::{error::InterpreterError, Interpreter},
+        parser::Parser,
+        scanner::Scanner,
+        vm::{compiler::Compiler, Vm},
+    };
+
+    #[test]
+    fn test_interrupt() {
+        let arena = Arena::new();
+        let parse = |source| {
+            let tokens = Scanner::new(source).scan_tokens().unwrap();
+            Parser::new(tokens, &arena).parse().unwrap()
+        };
+        let interrupted = |result| matches!(result, Err(InterpreterError::RuntimeError(msg)) if msg == "Interrupted");
+
+        let mut interpreter = Interpreter::default();
+        interpreter.interpret(&parse("var a = 1;")).unwrap();
+        interpreter.interrupt().request();
+        assert!(interrupted(interpreter.interpret(&parse("a = 2;"))));
+        // the request is handled once, and the variables are kept
+        interpreter.interpret(&parse("a = a + 2;")).unwrap();
+        assert_eq!(interpreter.environment().to_string(), "a = 3\n");
+
+        let mut vm = Vm::default();
+        vm.run(&Compiler::compile(&parse("var a = 1;")).unwrap())
+            .unwrap();
+        vm.interrupt().request();
+        assert!(interrupted(
+            vm.run(&Compiler::compile(&parse("a = 2;")).unwrap())
+        ));
+        vm.run(&Compiler::compile(&parse("a = a + 2;")).unwrap())
+            .unwrap();
+        assert_eq!(vm.to_string(), "a = 3\n");
+
+        // each of them has its own request
+        interpreter.interrupt().request();
+        vm.run(&Compiler::compile(&parse("a = 4;")).unwrap())
+            .unwrap();
+    }
+}
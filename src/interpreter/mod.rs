@@ -1,27 +1,39 @@
 pub mod environment;
 pub mod error;
+pub mod interrupt;
+pub mod operators;
+pub mod resolver;
+pub mod value;
 
-use std::mem;
+use std::rc::Rc;
 
 use self::{
     environment::Environment,
     error::{InterpreterError, InterpreterResult},
+    interrupt::Interrupt,
+    resolver::{Resolution, Resolver},
 };
-use crate::{
-    ast::{
-        expression::{Assign, Binary, Grouping, Literal, Unary, Variable},
-        statement::{BlockStatement, ExpressionStatement, PrintStatement, VariableStatement},
-        visitor::{ExprVisitor, StatementVisitor},
-        Expr,
-        Statement,
+use crate::ast::{
+    expression::{Assign, Binary, ErrorExpr, Grouping, Literal, LiteralValue, Unary, Variable},
+    statement::{
+        BlockStatement,
+        ErrorStatement,
+        ExpressionStatement,
+        PrintStatement,
+        VariableStatement,
     },
-    token::TokenType,
+    visitor::{ExprVisitor, StatementVisitor},
+    Expr,
+    NodeId,
+    NodeMap,
+    Statement,
 };
 
 #[derive(Debug, PartialEq, Clone)]
 pub enum EvaluatedExpr {
     Nil,
-    String(String),
+    /// Shared so that copying a value never copies the string, literals are interned
+    String(Rc<str>),
     Number(f64),
     Boolean(bool),
 }
@@ -37,54 +49,109 @@ impl ToString for EvaluatedExpr {
     }
 }
 
+impl EvaluatedExpr {
+    /// Representation of the value showing its type, as echoed by the REPL: unlike when printed,
+    /// strings are quoted
+    pub fn repr(&self) -> String {
+        match self {
+            EvaluatedExpr::String(v) => format!("{v:?}"),
+            v => v.to_string(),
+        }
+    }
+}
+
+impl From<&LiteralValue> for EvaluatedExpr {
+    fn from(literal: &LiteralValue) -> Self {
+        match literal {
+            LiteralValue::Boolean(v) => EvaluatedExpr::Boolean(*v),
+            LiteralValue::String(v) => EvaluatedExpr::String(v.clone()),
+            LiteralValue::Nil => EvaluatedExpr::Nil,
+            LiteralValue::Number(v) => EvaluatedExpr::Number(*v),
+        }
+    }
+}
+
+impl From<EvaluatedExpr> for LiteralValue {
+    fn from(value: EvaluatedExpr) -> Self {
+        match value {
+            EvaluatedExpr::Nil => LiteralValue::Nil,
+            EvaluatedExpr::String(v) => LiteralValue::String(v),
+            EvaluatedExpr::Number(v) => LiteralValue::Number(v),
+            EvaluatedExpr::Boolean(v) => LiteralValue::Boolean(v),
+        }
+    }
+}
+
 #[derive(Default)]
 pub struct Interpreter {
     environment: Environment,
+    /// Resolutions of the variables of the program being interpreted
+    resolutions: NodeMap<Resolution>,
+    interrupt: Interrupt,
 }
 
 impl Interpreter {
     pub fn interpret(&mut self, statements: &[Statement]) -> InterpreterResult<()> {
+        self.interpret_resolved(statements, Resolver::resolve(statements))
+    }
+
+    /// Interpret a program whose variables were already resolved, e.g. loaded from the cache
+    pub fn interpret_resolved(
+        &mut self,
+        statements: &[Statement],
+        resolutions: NodeMap<Resolution>,
+    ) -> InterpreterResult<()> {
+        self.resolutions = resolutions;
         for s in statements.iter() {
             self.execute(s)?
         }
         Ok(())
     }
+
+    /// Request to stop the running program
+    pub fn interrupt(&self) -> &Interrupt {
+        &self.interrupt
+    }
+
+    /// Variables defined by the programs interpreted so far
+    pub fn environment(&self) -> &Environment {
+        &self.environment
+    }
+
+    /// Interpret a program typed in the REPL, printing the [`EvaluatedExpr::repr`] of the value of
+    /// its expression statements which aren't in a block
+    pub fn interpret_echoing(&mut self, statements: &[Statement]) -> InterpreterResult<()> {
+        self.resolutions = Resolver::resolve(statements);
+        for s in statements.iter() {
+            match s {
+                Statement::Expression(statement) => {
+                    self.interrupt.check()?;
+                    println!("{}", self.evaluate(&statement.expr)?.repr())
+                }
+                s => self.execute(s)?,
+            }
+        }
+        Ok(())
+    }
+
     fn evaluate(&mut self, expr: &Expr) -> InterpreterResult<EvaluatedExpr> {
         expr.accept(self)
     }
     fn execute(&mut self, statement: &Statement) -> InterpreterResult<()> {
+        self.interrupt.check()?;
         statement.accept(self)
     }
 
-    fn execute_block(
-        &mut self,
-        statements: &[Statement],
-        environment: Environment,
-    ) -> InterpreterResult<()> {
-        let previous = mem::replace(&mut self.environment, environment);
-        self.environment.set_enclosing(previous);
-
-        for statement in statements.iter() {
-            self.execute(statement).map_err(|err| {
-                // in case any execute statement fails, let's unwind the environment properly
-                let previous = self.environment.take_enclosing();
-                self.environment = previous;
-                err
-            })?;
-        }
-
-        let previous = self.environment.take_enclosing();
-        self.environment = previous;
-        Ok(())
+    fn execute_block(&mut self, statements: &[Statement]) -> InterpreterResult<()> {
+        self.environment.push_frame();
+        let result = statements.iter().try_for_each(|s| self.execute(s));
+        // the frame is dropped even if a statement failed, so the environment is left unwound
+        self.environment.pop_frame();
+        result
     }
-}
 
-fn is_truthy(value: &EvaluatedExpr) -> bool {
-    match value {
-        EvaluatedExpr::Nil => false,
-        EvaluatedExpr::String(_) => true,
-        EvaluatedExpr::Number(_) => true,
-        EvaluatedExpr::Boolean(b) => *b,
+    fn resolution(&self, id: NodeId) -> Resolution {
+        self.resolutions[&id]
     }
 }
 
@@ -92,191 +159,53 @@ impl ExprVisitor for Interpreter {
     type Return = InterpreterResult<EvaluatedExpr>;
 
     fn visit_unary(&mut self, unary: &Unary) -> Self::Return {
-        let evaluated_right = self.evaluate(&unary.expr)?;
-
-        match unary.op.token_type {
-            TokenType::Minus => {
-                if let EvaluatedExpr::Number(v) = evaluated_right {
-                    Ok(EvaluatedExpr::Number(-v))
-                } else {
-                    Err(InterpreterError::TypeError(
-                        "Expected f64 after unary operator -".into(),
-                    ))
-                }
-            }
-            TokenType::Bang => Ok(EvaluatedExpr::Boolean(!is_truthy(&evaluated_right))),
-            t => Err(InterpreterError::TypeError(format!(
-                "Operand {t:?} not supported in unary expression"
-            ))),
-        }
+        let evaluated_right = self.evaluate(unary.expr)?;
+        operators::unary(unary.op.token_type, evaluated_right)
     }
 
     fn visit_binary(&mut self, binary: &Binary) -> Self::Return {
-        let evaluated_left = self.evaluate(&binary.left)?;
-        let evaluated_right = self.evaluate(&binary.right)?;
-
-        match binary.op.token_type {
-            TokenType::Minus => {
-                if let EvaluatedExpr::Number(l) = evaluated_left {
-                    if let EvaluatedExpr::Number(r) = evaluated_right {
-                        Ok(EvaluatedExpr::Number(l - r))
-                    } else {
-                        Err(InterpreterError::TypeError(
-                            "Right of - binary should be a valid number".into(),
-                        ))
-                    }
-                } else {
-                    Err(InterpreterError::TypeError(
-                        "Left of - binary should be a valid number".into(),
-                    ))
-                }
-            }
-            TokenType::Slash => {
-                if let EvaluatedExpr::Number(l) = evaluated_left {
-                    if let EvaluatedExpr::Number(r) = evaluated_right {
-                        Ok(EvaluatedExpr::Number(l / r))
-                    } else {
-                        Err(InterpreterError::TypeError(
-                            "Right of / binary should be a valid number".into(),
-                        ))
-                    }
-                } else {
-                    Err(InterpreterError::TypeError(
-                        "Left of / binary should be a valid number".into(),
-                    ))
-                }
-            }
-            TokenType::Star => {
-                if let EvaluatedExpr::Number(l) = evaluated_left {
-                    if let EvaluatedExpr::Number(r) = evaluated_right {
-                        Ok(EvaluatedExpr::Number(l * r))
-                    } else {
-                        Err(InterpreterError::TypeError(
-                            "Right of * binary should be a valid number".into(),
-                        ))
-                    }
-                } else {
-                    Err(InterpreterError::TypeError(
-                        "Left of * binary should be a valid number".into(),
-                    ))
-                }
-            }
-            TokenType::Plus => match evaluated_left {
-                EvaluatedExpr::Number(l) => {
-                    if let EvaluatedExpr::Number(r) = evaluated_right {
-                        Ok(EvaluatedExpr::Number(l + r))
-                    } else {
-                        Err(InterpreterError::TypeError(
-                            "Right of + binary should be a valid number when left is a number"
-                                .into(),
-                        ))
-                    }
-                }
-                EvaluatedExpr::String(l) => {
-                    if let EvaluatedExpr::String(r) = evaluated_right {
-                        Ok(EvaluatedExpr::String(format!("{l}{r}")))
-                    } else {
-                        Err(InterpreterError::TypeError(
-                            "Right of + binary should be a valid string when left is a string"
-                                .into(),
-                        ))
-                    }
-                }
-                _ => Err(InterpreterError::TypeError(
-                    "Cannot evaluate + operand, left expression should be a string or number"
-                        .into(),
-                )),
-            },
-            TokenType::Greater => {
-                if let EvaluatedExpr::Number(l) = evaluated_left {
-                    if let EvaluatedExpr::Number(r) = evaluated_right {
-                        Ok(EvaluatedExpr::Boolean(l > r))
-                    } else {
-                        Err(InterpreterError::TypeError(
-                            "Right of > binary should be a valid number".into(),
-                        ))
-                    }
-                } else {
-                    Err(InterpreterError::TypeError(
-                        "Left of > binary should be a valid number".into(),
-                    ))
-                }
-            }
-            TokenType::GreaterEqual => {
-                if let EvaluatedExpr::Number(l) = evaluated_left {
-                    if let EvaluatedExpr::Number(r) = evaluated_right {
-                        Ok(EvaluatedExpr::Boolean(l >= r))
-                    } else {
-                        Err(InterpreterError::TypeError(
-                            "Right of >= binary should be a valid number".into(),
-                        ))
-                    }
-                } else {
-                    Err(InterpreterError::TypeError(
-                        "Left of >= binary should be a valid number".into(),
-                    ))
-                }
-            }
-            TokenType::Less => {
-                if let EvaluatedExpr::Number(l) = evaluated_left {
-                    if let EvaluatedExpr::Number(r) = evaluated_right {
-                        Ok(EvaluatedExpr::Boolean(l < r))
-                    } else {
-                        Err(InterpreterError::TypeError(
-                            "Right of < binary should be a valid number".into(),
-                        ))
-                    }
-                } else {
-                    Err(InterpreterError::TypeError(
-                        "Left of < binary should be a valid number".into(),
-                    ))
-                }
-            }
-            TokenType::LessEqual => {
-                if let EvaluatedExpr::Number(l) = evaluated_left {
-                    if let EvaluatedExpr::Number(r) = evaluated_right {
-                        Ok(EvaluatedExpr::Boolean(l <= r))
-                    } else {
-                        Err(InterpreterError::TypeError(
-                            "Right of <= binary should be a valid number".into(),
-                        ))
-                    }
-                } else {
-                    Err(InterpreterError::TypeError(
-                        "Left of <= binary should be a valid number".into(),
-                    ))
-                }
-            }
-            TokenType::EqualEqual => Ok(EvaluatedExpr::Boolean(evaluated_left == evaluated_right)),
-            TokenType::BangEqual => Ok(EvaluatedExpr::Boolean(evaluated_left != evaluated_right)),
-            t => Err(InterpreterError::TypeError(format!(
-                "Operand {t:?} not supported in binary expression"
-            ))),
-        }
+        let evaluated_left = self.evaluate(binary.left)?;
+        let evaluated_right = self.evaluate(binary.right)?;
+        operators::binary(binary.op.token_type, evaluated_left, evaluated_right)
     }
 
     fn visit_grouping(&mut self, grouping: &Grouping) -> Self::Return {
-        self.evaluate(&grouping.expr)
+        self.evaluate(grouping.expr)
     }
 
     fn visit_literal(&mut self, literal: &Literal) -> Self::Return {
-        Ok(match literal {
-            Literal::Boolean(v) => EvaluatedExpr::Boolean(*v),
-            Literal::String(v) => EvaluatedExpr::String(v.clone()),
-            Literal::Nil => EvaluatedExpr::Nil,
-            Literal::Number(v) => EvaluatedExpr::Number(*v),
-        })
+        Ok((&literal.value).into())
     }
 
     fn visit_variable(&mut self, variable: &Variable) -> Self::Return {
-        self.environment.get(&variable.name)
+        match self.resolution(variable.id) {
+            Resolution::Local { depth, slot } => Ok(self.environment.get_local(depth, slot)),
+            _ => self.environment.get_global(&variable.name),
+        }
     }
 
     fn visit_assign(&mut self, assign: &Assign) -> Self::Return {
-        let value = self.evaluate(&assign.value)?;
-        self.environment.assign(&assign.name, value.clone())?;
+        let value = self.evaluate(assign.value)?;
+        match self.resolution(assign.id) {
+            Resolution::Local { depth, slot } => {
+                self.environment.assign_local(depth, slot, value.clone())
+            }
+            Resolution::Global => self
+                .environment
+                .assign_global(&assign.name, value.clone())?,
+            Resolution::Undefined => {
+                return Err(InterpreterError::RuntimeError(format!(
+                    "Undefined variable '{}'",
+                    assign.name.lexeme
+                )))
+            }
+        }
         Ok(value)
     }
+
+    fn visit_error(&mut self, error: &ErrorExpr) -> Self::Return {
+        Err(syntax_error(error.span.start_line))
+    }
 }
 
 impl StatementVisitor for Interpreter {
@@ -298,11 +227,24 @@ impl StatementVisitor for Interpreter {
         if let Some(init) = variable.initializer.as_ref() {
             value = self.evaluate(init)?;
         }
-        self.environment.define(variable.name.lexeme.clone(), value);
+        match self.resolution(variable.id) {
+            Resolution::Local { slot, .. } => self.environment.define_local(slot, value),
+            _ => self
+                .environment
+                .define_global(variable.name.lexeme.clone(), value),
+        }
         Ok(())
     }
 
     fn visit_block(&mut self, block: &BlockStatement) -> Self::Return {
-        self.execute_block(&block.statements, Environment::default())
+        self.execute_block(&block.statements)
     }
+
+    fn visit_error(&mut self, error: &ErrorStatement) -> Self::Return {
+        Err(syntax_error(error.span.start_line))
+    }
+}
+
+fn syntax_error(line: usize) -> InterpreterError {
+    InterpreterError::SyntaxError(format!("Cannot run code that failed to parse, line {line}"))
 }
@@ -0,0 +1,176 @@
+use super::{
+    error::{InterpreterError, InterpreterResult},
+    EvaluatedExpr,
+};
+use crate::token::TokenType;
+
+/// Apply an unary operator to its evaluated operand
+pub fn unary(op: TokenType, right: EvaluatedExpr) -> InterpreterResult<EvaluatedExpr> {
+    match op {
+        TokenType::Minus => {
+            if let EvaluatedExpr::Number(v) = right {
+                Ok(EvaluatedExpr::Number(-v))
+            } else {
+                Err(InterpreterError::TypeError(
+                    "Expected f64 after unary operator -".into(),
+                ))
+            }
+        }
+        TokenType::Bang => Ok(EvaluatedExpr::Boolean(!is_truthy(&right))),
+        t => Err(InterpreterError::TypeError(format!(
+            "Operand {t:?} not supported in unary expression"
+        ))),
+    }
+}
+
+/// Apply a binary operator to its evaluated operands
+pub fn binary(
+    op: TokenType,
+    left: EvaluatedExpr,
+    right: EvaluatedExpr,
+) -> InterpreterResult<EvaluatedExpr> {
+    match op {
+        TokenType::Minus => {
+            if let EvaluatedExpr::Number(l) = left {
+                if let EvaluatedExpr::Number(r) = right {
+                    Ok(EvaluatedExpr::Number(l - r))
+                } else {
+                    Err(InterpreterError::TypeError(
+                        "Right of - binary should be a valid number".into(),
+                    ))
+                }
+            } else {
+                Err(InterpreterError::TypeError(
+                    "Left of - binary should be a valid number".into(),
+                ))
+            }
+        }
+        TokenType::Slash => {
+            if let EvaluatedExpr::Number(l) = left {
+                if let EvaluatedExpr::Number(r) = right {
+                    Ok(EvaluatedExpr::Number(l / r))
+                } else {
+                    Err(InterpreterError::TypeError(
+                        "Right of / binary should be a valid number".into(),
+                    ))
+                }
+            } else {
+                Err(InterpreterError::TypeError(
+                    "Left of / binary should be a valid number".into(),
+                ))
+            }
+        }
+        TokenType::Star => {
+            if let EvaluatedExpr::Number(l) = left {
+                if let EvaluatedExpr::Number(r) = right {
+                    Ok(EvaluatedExpr::Number(l * r))
+                } else {
+                    Err(InterpreterError::TypeError(
+                        "Right of * binary should be a valid number".into(),
+                    ))
+                }
+            } else {
+                Err(InterpreterError::TypeError(
+                    "Left of * binary should be a valid number".into(),
+                ))
+            }
+        }
+        TokenType::Plus => match left {
+            EvaluatedExpr::Number(l) => {
+                if let EvaluatedExpr::Number(r) = right {
+                    Ok(EvaluatedExpr::Number(l + r))
+                } else {
+                    Err(InterpreterError::TypeError(
+                        "Right of + binary should be a valid number when left is a number".into(),
+                    ))
+                }
+            }
+            EvaluatedExpr::String(l) => {
+                if let EvaluatedExpr::String(r) = right {
+                    Ok(EvaluatedExpr::String(format!("{l}{r}").into()))
+                } else {
+                    Err(InterpreterError::TypeError(
+                        "Right of + binary should be a valid string when left is a string".into(),
+                    ))
+                }
+            }
+            _ => Err(InterpreterError::TypeError(
+                "Cannot evaluate + operand, left expression should be a string or number".into(),
+            )),
+        },
+        TokenType::Greater => {
+            if let EvaluatedExpr::Number(l) = left {
+                if let EvaluatedExpr::Number(r) = right {
+                    Ok(EvaluatedExpr::Boolean(l > r))
+                } else {
+                    Err(InterpreterError::TypeError(
+                        "Right of > binary should be a valid number".into(),
+                    ))
+                }
+            } else {
+                Err(InterpreterError::TypeError(
+                    "Left of > binary should be a valid number".into(),
+                ))
+            }
+        }
+        TokenType::GreaterEqual => {
+            if let EvaluatedExpr::Number(l) = left {
+                if let EvaluatedExpr::Number(r) = right {
+                    Ok(EvaluatedExpr::Boolean(l >= r))
+                } else {
+                    Err(InterpreterError::TypeError(
+                        "Right of >= binary should be a valid number".into(),
+                    ))
+                }
+            } else {
+                Err(InterpreterError::TypeError(
+                    "Left of >= binary should be a valid number".into(),
+                ))
+            }
+        }
+        TokenType::Less => {
+            if let EvaluatedExpr::Number(l) = left {
+                if let EvaluatedExpr::Number(r) = right {
+                    Ok(EvaluatedExpr::Boolean(l < r))
+                } else {
+                    Err(InterpreterError::TypeError(
+                        "Right of < binary should be a valid number".into(),
+                    ))
+                }
+            } else {
+                Err(InterpreterError::TypeError(
+                    "Left of < binary should be a valid number".into(),
+                ))
+            }
+        }
+        TokenType::LessEqual => {
+            if let EvaluatedExpr::Number(l) = left {
+                if let EvaluatedExpr::Number(r) = right {
+                    Ok(EvaluatedExpr::Boolean(l <= r))
+                } else {
+                    Err(InterpreterError::TypeError(
+                        "Right of <= binary should be a valid number".into(),
+                    ))
+                }
+            } else {
+                Err(InterpreterError::TypeError(
+                    "Left of <= binary should be a valid number".into(),
+                ))
+            }
+        }
+        TokenType::EqualEqual => Ok(EvaluatedExpr::Boolean(left == right)),
+        TokenType::BangEqual => Ok(EvaluatedExpr::Boolean(left != right)),
+        t => Err(InterpreterError::TypeError(format!(
+            "Operand {t:?} not supported in binary expression"
+        ))),
+    }
+}
+
+pub fn is_truthy(value: &EvaluatedExpr) -> bool {
+    match value {
+        EvaluatedExpr::Nil => false,
+        EvaluatedExpr::String(_) => true,
+        EvaluatedExpr::Number(_) => true,
+        EvaluatedExpr::Boolean(b) => *b,
+    }
+}
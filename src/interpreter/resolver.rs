@@ -0,0 +1,291 @@
+use std::rc::Rc;
+
+use crate::ast::{
+    expression::{Assign, Variable},
+    statement::{BlockStatement, VariableStatement},
+    visitor::walker::{self, Walker},
+    NodeId,
+    NodeMap,
+    Statement,
+};
+
+/// Where the variable named by a node lives at runtime
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Resolution {
+    /// In the `slot`th variable of the block `depth` blocks above the current one
+    Local { depth: usize, slot: usize },
+    /// In the globals, looked up by name
+    Global,
+    /// Nowhere the node can reach, e.g. an assignment to a variable of an enclosing block
+    Undefined,
+}
+
+/// Resolves every variable declaration, read and assignment of a program to the place its variable
+/// lives in at runtime, keyed by the identity of the node.
+///
+/// Scoping follows the tree-walking interpreter: reads look through every enclosing block then the
+/// globals, while assignments only reach the variables of the innermost block (or the globals at
+/// top level).
+#[derive(Default)]
+pub struct Resolver {
+    /// Names of the variables declared so far in each open block, indexed by slot
+    scopes: Vec<Vec<Rc<str>>>,
+    resolutions: NodeMap<Resolution>,
+}
+
+impl Resolver {
+    pub fn resolve(statements: &[Statement]) -> NodeMap<Resolution> {
+        let mut resolver = Resolver::default();
+        for statement in statements {
+            resolver.visit_statement(statement);
+        }
+        resolver.resolutions
+    }
+
+    /// Whether `resolutions` could have been made by resolving `statements`, so that running the
+    /// program with them never reaches a missing resolution or a variable out of its blocks. Meant
+    /// for resolutions which were stored away from their program, see [`crate::cache::Cache`].
+    pub fn is_valid(statements: &[Statement], resolutions: &NodeMap<Resolution>) -> bool {
+        let mut validator = Validator {
+            resolutions,
+            slots: Vec::new(),
+            valid: true,
+        };
+        for statement in statements {
+            validator.visit_statement(statement);
+        }
+        validator.valid
+    }
+
+    /// Slot of the variable with the given name in the scope `depth` blocks above the innermost
+    fn slot(&self, depth: usize, name: &str) -> Option<usize> {
+        let scope = &self.scopes[self.scopes.len() - 1 - depth];
+        scope.iter().position(|n| **n == *name)
+    }
+}
+
+impl Walker for Resolver {
+    fn visit_variable_statement(&mut self, statement: &VariableStatement) {
+        // the initializer is evaluated before the variable exists
+        walker::walk_variable_statement(self, statement);
+
+        let resolution = match self.scopes.last_mut() {
+            None => Resolution::Global,
+            Some(scope) => {
+                let name = &statement.name.lexeme;
+                // redeclaring a variable of the same block reuses its slot
+                let slot = scope.iter().position(|n| n == name).unwrap_or_else(|| {
+                    scope.push(name.clone());
+                    scope.len() - 1
+                });
+                Resolution::Local { depth: 0, slot }
+            }
+        };
+        self.resolutions.insert(statement.id, resolution);
+    }
+
+    fn visit_block_statement(&mut self, statement: &BlockStatement) {
+        self.scopes.push(Vec::new());
+        walker::walk_block_statement(self, statement);
+        self.scopes.pop();
+    }
+
+    fn visit_variable(&mut self, variable: &Variable) {
+        let resolution = (0..self.scopes.len())
+            .find_map(|depth| {
+                self.slot(depth, &variable.name.lexeme)
+                    .map(|slot| Resolution::Local { depth, slot })
+            })
+            .unwrap_or(Resolution::Global);
+        self.resolutions.insert(variable.id, resolution);
+    }
+
+    fn visit_assign(&mut self, assign: &Assign) {
+        walker::walk_assign(self, assign);
+
+        let resolution = if self.scopes.is_empty() {
+            Resolution::Global
+        } else {
+            match self.slot(0, &assign.name.lexeme) {
+                Some(slot) => Resolution::Local { depth: 0, slot },
+                None => Resolution::Undefined,
+            }
+        };
+        self.resolutions.insert(assign.id, resolution);
+    }
+}
+
+/// Checks resolutions against the blocks of their program, see [`Resolver::is_valid`]
+struct Validator<'r> {
+    resolutions: &'r NodeMap<Resolution>,
+    /// Number of slots defined so far in each open block
+    slots: Vec<usize>,
+    valid: bool,
+}
+
+impl Validator<'_> {
+    /// Whether the resolution of a read or an assignment reaches a defined variable
+    fn is_reachable(&self, id: NodeId) -> bool {
+        match self.resolutions.get(&id) {
+            Some(Resolution::Local { depth, slot }) => self
+                .slots
+                .len()
+                .checked_sub(depth + 1)
+                .map_or(false, |block| *slot < self.slots[block]),
+            Some(_) => true,
+            None => false,
+        }
+    }
+}
+
+impl Walker for Validator<'_> {
+    fn visit_variable_statement(&mut self, statement: &VariableStatement) {
+        walker::walk_variable_statement(self, statement);
+        match (self.resolutions.get(&statement.id), self.slots.last_mut()) {
+            // a variable either redefines a slot or takes the next free one
+            (Some(Resolution::Local { depth: 0, slot }), Some(defined)) if *slot <= *defined => {
+                *defined = (*defined).max(slot + 1)
+            }
+            (Some(Resolution::Global), _) => (),
+            _ => self.valid = false,
+        }
+    }
+
+    fn visit_block_statement(&mut self, statement: &BlockStatement) {
+        self.slots.push(0);
+        walker::walk_block_statement(self, statement);
+        self.slots.pop();
+    }
+
+    fn visit_variable(&mut self, variable: &Variable) {
+        self.valid &= self.is_reachable(variable.id);
+    }
+
+    fn visit_assign(&mut self, assign: &Assign) {
+        walker::walk_assign(self, assign);
+        self.valid &= self.is_reachable(assign.id);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Resolution, Resolver};
+    use crate::{
+        ast::{Arena, Expr, Statement},
+        parser::Parser,
+        scanner::Scanner,
+    };
+
+    fn parse<'a>(source: &str, arena: &'a Arena<'a>) -> Vec<Statement<'a>> {
+        let tokens = Scanner::new(source).scan_tokens().unwrap();
+        Parser::new(tokens, arena).parse().unwrap()
+    }
+
+    fn block<'a>(statement: &'a Statement<'a>) -> &'a [Statement<'a>] {
+        match statement {
+            Statement::Block(block) => &block.statements,
+            s => panic!("expected a block, got {s:?}"),
+        }
+    }
+
+    fn expr<'a>(statement: &'a Statement<'a>) -> &'a Expr<'a> {
+        match statement {
+            Statement::Expression(s) => &s.expr,
+            Statement::Print(s) => &s.expr,
+            s => panic!("expected an expression, got {s:?}"),
+        }
+    }
+
+    #[test]
+    fn test_reads_resolve_through_blocks() {
+        let arena = Arena::new();
+        let statements = parse(
+            "var g; { var a; var b; { var c; print b; print c; print g; } }",
+            &arena,
+        );
+        let resolutions = Resolver::resolve(&statements);
+
+        let outer = block(&statements[1]);
+        let inner = block(&outer[2]);
+        assert_eq!(resolutions[&statements[0].id()], Resolution::Global);
+        assert_eq!(
+            resolutions[&outer[1].id()],
+            Resolution::Local { depth: 0, slot: 1 }
+        );
+        assert_eq!(
+            resolutions[&expr(&inner[1]).id()],
+            Resolution::Local { depth: 1, slot: 1 }
+        );
+        assert_eq!(
+            resolutions[&expr(&inner[2]).id()],
+            Resolution::Local { depth: 0, slot: 0 }
+        );
+        assert_eq!(resolutions[&expr(&inner[3]).id()], Resolution::Global);
+    }
+
+    #[test]
+    fn test_shadowing_and_redeclaration() {
+        // the first read happens before the inner `a` is declared
+        let arena = Arena::new();
+        let statements = parse("{ var a; { print a; var a; var a; print a; } }", &arena);
+        let resolutions = Resolver::resolve(&statements);
+
+        let inner = block(&block(&statements[0])[1]);
+        assert_eq!(
+            resolutions[&expr(&inner[0]).id()],
+            Resolution::Local { depth: 1, slot: 0 }
+        );
+        assert_eq!(
+            resolutions[&inner[2].id()],
+            Resolution::Local { depth: 0, slot: 0 }
+        );
+        assert_eq!(
+            resolutions[&expr(&inner[3]).id()],
+            Resolution::Local { depth: 0, slot: 0 }
+        );
+    }
+
+    #[test]
+    fn test_assignments_only_reach_innermost_block() {
+        let arena = Arena::new();
+        let statements = parse("a = 1; { var b; b = 2; { b = 3; } }", &arena);
+        let resolutions = Resolver::resolve(&statements);
+
+        let outer = block(&statements[1]);
+        let inner = block(&outer[2]);
+        assert_eq!(resolutions[&expr(&statements[0]).id()], Resolution::Global);
+        assert_eq!(
+            resolutions[&expr(&outer[1]).id()],
+            Resolution::Local { depth: 0, slot: 0 }
+        );
+        assert_eq!(resolutions[&expr(&inner[0]).id()], Resolution::Undefined);
+    }
+
+    #[test]
+    fn test_is_valid() {
+        let arena = Arena::new();
+        let statements = parse(
+            "var a; { var b = a; { var c; print b + c; c = 1; } }",
+            &arena,
+        );
+        let resolutions = Resolver::resolve(&statements);
+        assert!(Resolver::is_valid(&statements, &resolutions));
+
+        let outer = block(&statements[1]);
+        let inner = block(&outer[1]);
+        let mut missing = resolutions.clone();
+        missing.remove(&expr(&inner[2]).id());
+        assert!(!Resolver::is_valid(&statements, &missing));
+
+        let mut too_deep = resolutions.clone();
+        too_deep.insert(
+            expr(&inner[2]).id(),
+            Resolution::Local { depth: 2, slot: 0 },
+        );
+        assert!(!Resolver::is_valid(&statements, &too_deep));
+
+        let mut undefined_slot = resolutions;
+        undefined_slot.insert(outer[0].id(), Resolution::Local { depth: 0, slot: 1 });
+        assert!(!Resolver::is_valid(&statements, &undefined_slot));
+    }
+}
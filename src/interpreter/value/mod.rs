@@ -0,0 +1,87 @@
+#[cfg(all(feature = "nan-boxing", not(target_pointer_width = "64")))]
+compile_error!("the nan-boxing feature stores pointers in 48 bits, it needs a 64-bit target");
+
+#[cfg(feature = "nan-boxing")]
+mod nan_boxed;
+#[cfg(not(feature = "nan-boxing"))]
+mod plain;
+
+use std::fmt;
+
+#[cfg(feature = "nan-boxing")]
+pub use self::nan_boxed::Value;
+#[cfg(not(feature = "nan-boxing"))]
+pub use self::plain::Value;
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.to_evaluated().to_string())
+    }
+}
+
+impl fmt::Debug for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.to_evaluated().fmt(f)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Value;
+    use crate::interpreter::EvaluatedExpr;
+
+    #[test]
+    fn test_round_trip() {
+        for value in [
+            EvaluatedExpr::Nil,
+            EvaluatedExpr::Boolean(true),
+            EvaluatedExpr::Boolean(false),
+            EvaluatedExpr::Number(0.0),
+            EvaluatedExpr::Number(-1.5),
+            EvaluatedExpr::Number(f64::INFINITY),
+            EvaluatedExpr::Number(f64::MIN_POSITIVE),
+            EvaluatedExpr::String("hello".into()),
+        ] {
+            let stored = Value::from(value.clone());
+            assert_eq!(stored.clone().to_evaluated(), value);
+            assert_eq!(stored.to_string(), value.to_string());
+        }
+    }
+
+    #[test]
+    fn test_nan() {
+        let stored = Value::number(-f64::NAN);
+        assert!(stored.as_number().unwrap().is_nan());
+        assert_eq!(stored.to_string(), "NaN");
+    }
+
+    #[test]
+    fn test_accessors() {
+        assert_eq!(Value::number(2.0).as_number(), Some(2.0));
+        assert_eq!(Value::nil().as_number(), None);
+        assert_eq!(
+            Value::boolean(true).to_evaluated(),
+            EvaluatedExpr::Boolean(true)
+        );
+        assert_eq!(
+            Value::from(EvaluatedExpr::String("a".into())).as_str(),
+            Some(&"a".into())
+        );
+        assert_eq!(Value::number(1.0).as_str(), None);
+    }
+
+    #[test]
+    fn test_strings_are_shared() {
+        let s: std::rc::Rc<str> = "shared".into();
+        let stored = Value::from(EvaluatedExpr::String(s.clone()));
+        let copy = stored.clone();
+        drop(stored);
+        assert!(std::rc::Rc::ptr_eq(copy.as_str().unwrap(), &s));
+    }
+
+    #[cfg(feature = "nan-boxing")]
+    #[test]
+    fn test_size() {
+        assert_eq!(std::mem::size_of::<Value>(), 8);
+    }
+}
@@ -0,0 +1,173 @@
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    marker::PhantomData,
+    rc::{Rc, Weak},
+};
+
+use crate::interpreter::EvaluatedExpr;
+
+/// Bits set in every value which isn't a number: the exponent, the quiet NaN bit and one more
+/// bit, so that no NaN produced by arithmetic is mistaken for another kind of value
+const QNAN: u64 = 0x7ffc_0000_0000_0000;
+/// Set along with `QNAN` in heap references, the low 48 bits hold the pointer
+const SIGN_BIT: u64 = 0x8000_0000_0000_0000;
+
+const NIL: u64 = QNAN | 1;
+const FALSE: u64 = QNAN | 2;
+const TRUE: u64 = QNAN | 3;
+
+/// Strings are held through a thin pointer, as the pointer of a `Rc<str>` also carries its length
+type Heap = Rc<str>;
+
+thread_local! {
+    /// Boxes of the strings held by values, by address of their content, so that a string is boxed
+    /// once however many times it's stored. An entry is removed with the last value holding it.
+    static BOXES: RefCell<HashMap<*const u8, Weak<Heap>>> = RefCell::new(HashMap::new());
+}
+
+/// Runtime value stored in variables and on the stack of the virtual machine, NaN-boxed in 64 bits.
+///
+/// Numbers are stored as their own bits. Every other value is stored in the space of quiet NaNs:
+/// nil and booleans as tags in the low bits, strings as a pointer to a reference counted box of
+/// their `Rc<str>` with the sign bit set, shared by every value holding the same string. NaNs are
+/// canonicalized when stored, so they never look like a tag.
+pub struct Value {
+    bits: u64,
+    /// values holding a `Rc` must be neither `Send` nor `Sync`
+    _heap: PhantomData<Rc<Heap>>,
+}
+
+impl Value {
+    pub fn nil() -> Self {
+        Self::from_bits(NIL)
+    }
+
+    pub fn boolean(v: bool) -> Self {
+        Self::from_bits(if v { TRUE } else { FALSE })
+    }
+
+    pub fn number(v: f64) -> Self {
+        if v.is_nan() {
+            Self::from_bits(f64::NAN.to_bits())
+        } else {
+            Self::from_bits(v.to_bits())
+        }
+    }
+
+    fn string(v: Rc<str>) -> Self {
+        let boxed = BOXES.with(|boxes| {
+            let mut boxes = boxes.borrow_mut();
+            if let Some(boxed) = boxes.get(&v.as_ptr()).and_then(Weak::upgrade) {
+                return boxed;
+            }
+            let key = v.as_ptr();
+            let boxed = Rc::new(v);
+            boxes.insert(key, Rc::downgrade(&boxed));
+            boxed
+        });
+        let ptr = Rc::into_raw(boxed) as u64;
+        assert_eq!(ptr & (QNAN | SIGN_BIT), 0, "pointer doesn't fit in 48 bits");
+        Self::from_bits(SIGN_BIT | QNAN | ptr)
+    }
+
+    fn from_bits(bits: u64) -> Self {
+        Value {
+            bits,
+            _heap: PhantomData,
+        }
+    }
+
+    pub fn as_number(&self) -> Option<f64> {
+        if self.bits & QNAN != QNAN {
+            Some(f64::from_bits(self.bits))
+        } else {
+            None
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&Rc<str>> {
+        // SAFETY: the pointer comes from `Rc::into_raw` and the reference held by `self` keeps it
+        // alive for as long as the returned borrow
+        self.heap_ptr().map(|ptr| unsafe { &*ptr })
+    }
+
+    pub fn to_evaluated(&self) -> EvaluatedExpr {
+        if let Some(v) = self.as_number() {
+            return EvaluatedExpr::Number(v);
+        }
+        if let Some(v) = self.as_str() {
+            return EvaluatedExpr::String(v.clone());
+        }
+        match self.bits {
+            NIL => EvaluatedExpr::Nil,
+            FALSE => EvaluatedExpr::Boolean(false),
+            TRUE => EvaluatedExpr::Boolean(true),
+            bits => unreachable!("invalid value bits {bits:#x}"),
+        }
+    }
+
+    fn heap_ptr(&self) -> Option<*const Heap> {
+        if self.bits & (QNAN | SIGN_BIT) == QNAN | SIGN_BIT {
+            Some((self.bits & !(QNAN | SIGN_BIT)) as *const Heap)
+        } else {
+            None
+        }
+    }
+}
+
+impl Clone for Value {
+    fn clone(&self) -> Self {
+        if let Some(ptr) = self.heap_ptr() {
+            // SAFETY: the pointer comes from `Rc::into_raw` and is kept alive by `self`
+            unsafe { Rc::increment_strong_count(ptr) };
+        }
+        Self::from_bits(self.bits)
+    }
+}
+
+impl Drop for Value {
+    fn drop(&mut self) {
+        if let Some(ptr) = self.heap_ptr() {
+            // SAFETY: the pointer comes from `Rc::into_raw`, `self` owns one of its references
+            let boxed = unsafe { Rc::from_raw(ptr) };
+            if Rc::strong_count(&boxed) == 1 {
+                // the boxes may already be gone if the thread is exiting
+                let _ = BOXES.try_with(|boxes| boxes.borrow_mut().remove(&boxed.as_ptr()));
+            }
+        }
+    }
+}
+
+impl From<EvaluatedExpr> for Value {
+    fn from(value: EvaluatedExpr) -> Self {
+        match value {
+            EvaluatedExpr::Nil => Value::nil(),
+            EvaluatedExpr::Boolean(v) => Value::boolean(v),
+            EvaluatedExpr::Number(v) => Value::number(v),
+            EvaluatedExpr::String(v) => Value::string(v),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::rc::Rc;
+
+    use super::{Value, BOXES};
+    use crate::interpreter::EvaluatedExpr;
+
+    #[test]
+    fn test_strings_are_boxed_once() {
+        let s: Rc<str> = "boxed".into();
+        let a = Value::from(EvaluatedExpr::String(s.clone()));
+        let b = Value::from(EvaluatedExpr::String(s.clone()));
+        assert_eq!(a.bits, b.bits);
+
+        drop(a);
+        assert!(BOXES.with(|boxes| boxes.borrow().contains_key(&s.as_ptr())));
+        drop(b);
+        assert!(!BOXES.with(|boxes| boxes.borrow().contains_key(&s.as_ptr())));
+        assert_eq!(Rc::strong_count(&s), 1);
+    }
+}
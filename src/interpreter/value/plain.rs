@@ -0,0 +1,48 @@
+use std::rc::Rc;
+
+use crate::interpreter::EvaluatedExpr;
+
+/// Runtime value stored in variables and on the stack of the virtual machine, as a plain
+/// [`EvaluatedExpr`].
+///
+/// Enable the `nan-boxing` feature to store values in 64 bits instead.
+#[derive(Clone)]
+pub struct Value(EvaluatedExpr);
+
+impl Value {
+    pub fn nil() -> Self {
+        Value(EvaluatedExpr::Nil)
+    }
+
+    pub fn boolean(v: bool) -> Self {
+        Value(EvaluatedExpr::Boolean(v))
+    }
+
+    pub fn number(v: f64) -> Self {
+        Value(EvaluatedExpr::Number(v))
+    }
+
+    pub fn as_number(&self) -> Option<f64> {
+        match self.0 {
+            EvaluatedExpr::Number(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&Rc<str>> {
+        match &self.0 {
+            EvaluatedExpr::String(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    pub fn to_evaluated(&self) -> EvaluatedExpr {
+        self.0.clone()
+    }
+}
+
+impl From<EvaluatedExpr> for Value {
+    fn from(value: EvaluatedExpr) -> Self {
+        Value(value)
+    }
+}
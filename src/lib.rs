@@ -0,0 +1,16 @@
+pub mod ast;
+pub mod cache;
+pub mod check;
+pub mod error;
+pub mod incremental;
+pub mod interner;
+pub mod interpreter;
+pub mod optimizer;
+pub mod parser;
+pub mod repl;
+pub mod rox;
+pub mod scanner;
+pub mod token;
+pub mod transpiler;
+pub mod vm;
+pub mod watch;
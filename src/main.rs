@@ -1,17 +1,17 @@
-mod ast;
-mod error;
-mod interpreter;
-mod parser;
-mod rox;
-mod scanner;
-mod token;
 use std::process;
 
 use camino::Utf8PathBuf;
-use clap::Parser;
+use clap::{Parser, Subcommand};
 use env_logger::Builder;
 use log::LevelFilter;
-use rox::Rox;
+use rox::{
+    cache::Cache,
+    check,
+    optimizer::OptimizationLevel,
+    repl::LineReader,
+    rox::{Backend, Emit, InputFormat, Rox, Target},
+    watch,
+};
 
 /// Here's my app!
 #[derive(Debug, Parser)]
@@ -20,9 +20,82 @@ pub struct App {
     #[clap(long, short, global = true, parse(from_occurrences))]
     verbose: usize,
 
-    /// optional path to file to interpret, if none is specified, REPL
+    /// print an intermediate representation of the file instead of running it
+    #[clap(long, arg_enum, requires = "file-to-run")]
+    emit: Option<Emit>,
+
+    /// optimization level: 0 runs the program as parsed, 1 folds constant expressions
+    #[clap(short = 'O', default_value = "1")]
+    optimization_level: OptimizationLevel,
+
+    /// engine executing the program
+    #[clap(long, arg_enum, default_value = "tree")]
+    backend: Backend,
+
+    /// print the bytecode the file compiles to instead of running it
+    #[clap(long, requires = "file-to-run", conflicts_with = "emit")]
+    disassemble: bool,
+
+    /// always scan and parse the file, instead of loading it from the cache of parsed programs
+    /// (stored in $ROX_CACHE_DIR, defaulting to the user's cache directory)
+    #[clap(long)]
+    no_cache: bool,
+
+    /// format of the file to interpret
+    #[clap(long, arg_enum, default_value = "lox")]
+    input: InputFormat,
+
+    /// program to run, given on the command line instead of in a file
+    #[clap(long, short, conflicts_with_all = &["file-to-run", "emit", "disassemble"])]
+    eval: Option<String>,
+
+    /// optional path to file to interpret, `-` for stdin, if none is specified, REPL
     /// interpreter starts
     file_to_run: Option<Utf8PathBuf>,
+
+    #[clap(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Compile a file to another language instead of running it
+    ///
+    /// The compiled program prints what `rox <FILE>` prints, but stops with exit status 70 on a
+    /// runtime error, where `rox <FILE>` reports the error and exits with 0.
+    Compile {
+        /// language to compile to
+        #[clap(long, arg_enum)]
+        target: Target,
+
+        /// path of the file to write, the compiled program is printed if none is specified
+        #[clap(long, short)]
+        output: Option<Utf8PathBuf>,
+
+        file: Utf8PathBuf,
+    },
+    /// Run a file, like `rox <FILE>`
+    Run {
+        /// run the file again whenever it changes
+        #[clap(long)]
+        watch: bool,
+
+        /// clear the screen before running the file again
+        #[clap(long, requires = "watch")]
+        clear: bool,
+
+        /// directory whose changes also run the file again, recursively
+        #[clap(long = "watch-dir", requires = "watch", multiple_occurrences = true)]
+        watch_dirs: Vec<Utf8PathBuf>,
+
+        file: Utf8PathBuf,
+    },
+    /// Scan, parse and analyze programs without running them, reporting every error found
+    Check {
+        /// files to check, and directories whose `.rox` files are checked
+        #[clap(required = true)]
+        paths: Vec<Utf8PathBuf>,
+    },
 }
 
 fn main() {
@@ -37,18 +110,101 @@ fn main() {
     };
     builder.init();
 
-    let mut interpreter = Rox::default();
-    if let Some(file_to_run) = opts.file_to_run {
-        if let Err(e) = interpreter.run_file(file_to_run) {
+    if let Some(Command::Check { paths }) = &opts.command {
+        let mut failed = false;
+        for report in check::check_paths(paths) {
+            for diagnostic in report.diagnostics.iter() {
+                eprintln!("{}: {}", report.path, diagnostic);
+            }
+            failed |= !report.diagnostics.is_empty();
+        }
+        process::exit(if failed { 65 } else { 0 })
+    }
+
+    let mut interpreter = new_interpreter(&opts);
+    if let Some(Command::Compile {
+        target,
+        output,
+        file,
+    }) = &opts.command
+    {
+        if let Err(e) = interpreter.compile_file(file.clone(), *target, output.clone()) {
+            eprintln!("{}", e);
+            process::exit(65);
+        }
+        process::exit(0)
+    }
+
+    if let Some(Command::Run {
+        watch,
+        clear,
+        watch_dirs,
+        file,
+    }) = &opts.command
+    {
+        if !watch {
+            process::exit(run_file(&opts, &mut interpreter, file.clone()));
+        }
+        // every run starts from a fresh interpreter
+        let result = watch::watch(file, watch_dirs, *clear, || {
+            run_file(&opts, &mut new_interpreter(&opts), file.clone())
+        });
+        if let Err(e) = result {
             eprintln!("{}", e);
             process::exit(65);
         }
         process::exit(0)
     }
 
-    if let Err(e) = interpreter.run_prompt() {
+    if let Some(source) = &opts.eval {
+        let result = match opts.input {
+            InputFormat::Lox => interpreter.run_source(source),
+            InputFormat::AstJson => interpreter.run_ast_json(source),
+        };
+        if let Err(e) = result {
+            eprintln!("{}", e);
+            process::exit(65);
+        }
+        process::exit(0)
+    }
+
+    if let Some(file_to_run) = opts.file_to_run.clone() {
+        process::exit(run_file(&opts, &mut interpreter, file_to_run))
+    }
+
+    let result = LineReader::new(LineReader::default_history())
+        .and_then(|mut reader| interpreter.run_prompt(&mut reader));
+    if let Err(e) = result {
         eprintln!("{}", e);
         process::exit(65);
     }
     process::exit(0);
 }
+
+/// Interpreter set up with the options of the command line
+fn new_interpreter(opts: &App) -> Rox {
+    let interpreter = Rox::new(opts.optimization_level, opts.backend);
+    if opts.no_cache {
+        return interpreter;
+    }
+    interpreter.with_cache(Cache::new(Cache::default_dir()))
+}
+
+/// Run the file, or print what the options ask for instead, returning the exit status
+fn run_file(opts: &App, interpreter: &mut Rox, file: Utf8PathBuf) -> i32 {
+    let result = match (opts.emit, opts.input) {
+        _ if opts.disassemble => interpreter.disassemble_file(file),
+        (Some(emit), InputFormat::Lox) => interpreter.emit_file(file, emit),
+        (None, InputFormat::Lox) => interpreter.run_file(file),
+        (None, InputFormat::AstJson) => interpreter.run_ast_json_file(file),
+        (Some(_), InputFormat::AstJson) => {
+            eprintln!("--emit can only be used on Lox source files");
+            return 64;
+        }
+    };
+    if let Err(e) = result {
+        eprintln!("{}", e);
+        return 65;
+    }
+    0
+}
@@ -0,0 +1,109 @@
+use crate::{
+    ast::{
+        expression::{Binary, Grouping, Unary},
+        visitor::Folder,
+        Arena,
+        Expr,
+    },
+    interpreter::operators,
+};
+
+/// Folds the subexpressions only made of literals into the literal they evaluate to, and removes
+/// groupings which are only needed to parse the expressions.
+///
+/// Folding goes through the same operators as the interpreter. When an operator fails on its
+/// literal operands, the subexpression is left untouched so that the interpreter reports the error
+/// when it executes it, exactly as without folding.
+pub struct ConstantFolder<'a> {
+    arena: &'a Arena<'a>,
+}
+
+impl<'a> ConstantFolder<'a> {
+    pub fn new(arena: &'a Arena<'a>) -> Self {
+        Self { arena }
+    }
+}
+
+impl<'a> Folder<'a> for ConstantFolder<'a> {
+    fn arena(&self) -> &'a Arena<'a> {
+        self.arena
+    }
+
+    fn fold_grouping(&mut self, grouping: Grouping<'a>) -> Expr<'a> {
+        self.fold_expr(grouping.expr.clone())
+    }
+
+    fn fold_unary(&mut self, unary: Unary<'a>) -> Expr<'a> {
+        let expr = self.fold_expr(unary.expr.clone());
+        if let Expr::Literal(literal) = &expr {
+            if let Ok(value) = operators::unary(unary.op.token_type, (&literal.value).into()) {
+                return Expr::new_literal(value.into(), unary.span);
+            }
+        }
+
+        Expr::Unary(Unary {
+            expr: self.arena.alloc(expr),
+            ..unary
+        })
+    }
+
+    fn fold_binary(&mut self, binary: Binary<'a>) -> Expr<'a> {
+        let left = self.fold_expr(binary.left.clone());
+        let right = self.fold_expr(binary.right.clone());
+        if let (Expr::Literal(l), Expr::Literal(r)) = (&left, &right) {
+            if let Ok(value) =
+                operators::binary(binary.op.token_type, (&l.value).into(), (&r.value).into())
+            {
+                return Expr::new_literal(value.into(), binary.span);
+            }
+        }
+
+        Expr::Binary(Binary {
+            left: self.arena.alloc(left),
+            right: self.arena.alloc(right),
+            ..binary
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        ast::{visitor::ASTPrettyPrinter, Arena},
+        optimizer::{optimize, OptimizationLevel},
+        parser::Parser,
+        scanner::Scanner,
+    };
+
+    fn optimized(source: &str) -> String {
+        let tokens = Scanner::new(source).scan_tokens().unwrap();
+        let arena = Arena::new();
+        let statements = Parser::new(tokens, &arena).parse().unwrap();
+        ASTPrettyPrinter::new().print(&optimize(statements, OptimizationLevel::O1, &arena))
+    }
+
+    #[test]
+    fn test_folds_literals() {
+        assert_eq!(optimized("60 * 60 * 24;"), "86400");
+        assert_eq!(optimized("print \"a\" + \"b\";"), "print\n└─ ab");
+        assert_eq!(optimized("-(2 - 4) >= 2 == !nil;"), "true");
+    }
+
+    #[test]
+    fn test_folds_around_variables() {
+        assert_eq!(
+            optimized("var a = (1 + 2) * (a - -1);"),
+            "var a\n└─ *\n│  └─ 3\n│  └─ -\n│  │  └─ a\n│  │  └─ -1"
+        );
+        assert_eq!(optimized("{ b = ((b)); }"), "block\n└─ b =\n│  └─ b");
+    }
+
+    #[test]
+    fn test_keeps_type_errors_for_runtime() {
+        assert_eq!(
+            optimized("print (1 + 2) + \"a\";"),
+            "print\n└─ +\n│  └─ 3\n│  └─ a"
+        );
+        assert_eq!(optimized("-\"a\";"), "-\n└─ a");
+    }
+}
@@ -0,0 +1,48 @@
+mod constant_folder;
+
+use std::str::FromStr;
+
+pub use constant_folder::ConstantFolder;
+
+use crate::ast::{visitor::Folder, Arena, Statement};
+
+/// Amount of optimisation applied to a program between parsing and execution
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
+pub enum OptimizationLevel {
+    /// The program is executed as parsed
+    O0,
+    /// Constant subexpressions are folded and groupings removed, see [`ConstantFolder`]
+    #[default]
+    O1,
+}
+
+impl FromStr for OptimizationLevel {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "0" => Ok(OptimizationLevel::O0),
+            "1" => Ok(OptimizationLevel::O1),
+            _ => Err(format!("unknown optimization level {s}, expected 0 or 1")),
+        }
+    }
+}
+
+/// Run the optimisation passes of the given level over a program, allocating the rewritten
+/// expressions in the arena of the program
+pub fn optimize<'a>(
+    statements: Vec<Statement<'a>>,
+    level: OptimizationLevel,
+    arena: &'a Arena<'a>,
+) -> Vec<Statement<'a>> {
+    match level {
+        OptimizationLevel::O0 => statements,
+        OptimizationLevel::O1 => {
+            let mut folder = ConstantFolder::new(arena);
+            statements
+                .into_iter()
+                .map(|s| folder.fold_statement(s))
+                .collect()
+        }
+    }
+}
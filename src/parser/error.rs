@@ -3,7 +3,7 @@ use std::fmt::Display;
 use thiserror::Error;
 
 use crate::token::{Token, TokenType};
-#[derive(Error, Debug, PartialEq)]
+#[derive(Error, Debug, PartialEq, Clone)]
 pub struct ParserError {
     token: Token,
     msg: String,
@@ -13,6 +13,15 @@ impl ParserError {
     pub fn new(token: Token, msg: String) -> Self {
         Self { token, msg }
     }
+
+    /// Token the error is reported at
+    pub fn token(&self) -> &Token {
+        &self.token
+    }
+
+    pub fn msg(&self) -> &str {
+        &self.msg
+    }
 }
 
 impl Display for ParserError {
@@ -1,11 +1,15 @@
 pub mod error;
+mod precedence;
 
 use error::ParserError;
 
-use self::error::ParserResults;
+use self::{
+    error::ParserResults,
+    precedence::{InfixOperator, Precedence, PrefixOperator},
+};
 use crate::{
-    ast::{Expr, Statement},
-    token::{Token, TokenType},
+    ast::{Arena, Expr, NodeMap, Statement},
+    token::{Span, Token, TokenType},
 };
 
 /// Implements the parsing of tokens obtained from the scanner into an AST,
@@ -20,70 +24,151 @@ use crate::{
 /// print_statement       → print expression  ";" ;
 /// block                 → "{" declaration* "}" ;
 ///
-/// expression            → assignment ;
-/// assignment            → IDENTIFIER "=" assignment | equality ;
-/// equality              → comparison ( ( "!=" | "==" ) comparison )* ;
-/// comparison            → term ( ( ">" | ">=" | "<" | "<=" ) term )* ;
-/// term                  → factor ( ( "-" | "+" ) factor )* ;
-/// factor                → unary ( ( "/" | "*" ) unary )* ;
-/// unary                 → ( "!" | "-" ) unary | primary ;
+/// expression            → prefix ( INFIX_OPERATOR expression )* ;
+/// prefix                → PREFIX_OPERATOR prefix | primary ;
 /// primary               → NUMBER | STRING | "true" | "false" | "nil" | "(" expression ")"
 ///                         | IDENTIFIER ;
-pub struct Parser {
+///
+/// Operators are parsed by precedence climbing, their precedence, associativity and the node they
+/// build are given by the tables of the `precedence` module: from the loosest, assignment (right
+/// associative, to a variable only), equality, comparison, term, factor, then the unary "!" and
+/// "-".
+///
+/// Errors are recovered from so that a single pass reports all of them along with a best-effort
+/// AST: a statement that fails to parse is replaced by an error node spanning the tokens skipped
+/// up to the next statement, without leaving the enclosing block, and the content of parentheses
+/// that fails to parse is replaced by an error node when the parentheses are closed.
+///
+/// The expressions are allocated in the arena the parser is given, which the AST borrows.
+pub struct Parser<'a> {
     /// Holds the list of tokens being parsed
     tokens: Vec<Token>,
     /// Internal state: keep track of the current token index
     current_index: usize,
+    /// Errors encountered so far, in the order of the source
+    errors: Vec<ParserError>,
+    /// Number of blocks the current token is in
+    block_depth: usize,
+    /// Number of errors encountered while parsing each declaration, including the ones of the
+    /// declarations it contains, only for the declarations having errors
+    error_counts: NodeMap<usize>,
+    /// Where the expressions are allocated
+    arena: &'a Arena<'a>,
 }
 
-impl Parser {
-    /// Builds a parser from a Vec of tokens obtained from the scanner
-    pub fn new(tokens: Vec<Token>) -> Self {
+impl<'a> Parser<'a> {
+    /// Builds a parser from a Vec of tokens obtained from the scanner, allocating the expressions
+    /// in `arena`
+    pub fn new(tokens: Vec<Token>, arena: &'a Arena<'a>) -> Self {
         Self {
             tokens,
             current_index: 0,
+            errors: Vec::new(),
+            block_depth: 0,
+            error_counts: NodeMap::default(),
+            arena,
         }
     }
 
-    /// Parse the given tokens into an AST using the rules of the grammer
-    pub fn parse(mut self) -> ParserResults<Vec<Statement>> {
-        let mut statements = Vec::new();
-        let mut errors_encountered: Vec<ParserError> = Vec::new();
-
-        while self.peek().token_type != TokenType::Eof {
-            match self.declaration() {
-                Ok(s) => statements.push(s),
-                Err(e) => errors_encountered.push(e),
-            };
+    /// Builds a parser resuming at the token at `index`, which starts a declaration inside
+    /// `block_depth` blocks, to parse again a part of a source
+    pub(crate) fn resume(
+        tokens: Vec<Token>,
+        arena: &'a Arena<'a>,
+        index: usize,
+        block_depth: usize,
+    ) -> Self {
+        Self {
+            current_index: index,
+            block_depth,
+            ..Self::new(tokens, arena)
         }
+    }
+
+    /// Next token to parse
+    pub(crate) fn current(&self) -> &Token {
+        self.peek()
+    }
+
+    /// Whether the declarations of the current block, or of the program outside of any block,
+    /// are all parsed
+    pub(crate) fn at_end_of_declarations(&self) -> bool {
+        self.check(TokenType::Eof) || (self.block_depth > 0 && self.check(TokenType::RightBrace))
+    }
 
-        if !errors_encountered.is_empty() {
-            Err(errors_encountered)
+    /// Parse the declaration starting at the current token
+    pub(crate) fn next_declaration(&mut self) -> Statement<'a> {
+        self.declaration()
+    }
+
+    /// Give back the tokens, along with the errors encountered and their number for each
+    /// declaration having some
+    pub(crate) fn into_parts(self) -> (Vec<Token>, Vec<ParserError>, NodeMap<usize>) {
+        (self.tokens, self.errors, self.error_counts)
+    }
+
+    /// Parse the given tokens into an AST using the rules of the grammer
+    pub fn parse(self) -> ParserResults<Vec<Statement<'a>>> {
+        let (statements, errors) = self.parse_partial();
+        if !errors.is_empty() {
+            Err(errors)
         } else {
             Ok(statements)
         }
     }
 
+    /// Parse the given tokens into a best-effort AST, where what failed to parse is replaced by
+    /// error nodes, along with the errors encountered
+    pub fn parse_partial(mut self) -> (Vec<Statement<'a>>, Vec<ParserError>) {
+        let mut statements = Vec::new();
+        while self.peek().token_type != TokenType::Eof {
+            statements.push(self.declaration());
+        }
+        (statements, self.errors)
+    }
+
     // Grammar rules
 
     /// Defines the rule to parse the declaration rule in the grammar:
     /// declaration           → var_decl | statement ;
-    fn declaration(&mut self) -> Result<Statement, ParserError> {
+    ///
+    /// A declaration which fails to parse is replaced by an error node
+    fn declaration(&mut self) -> Statement<'a> {
+        let errors_before = self.errors.len();
+        let statement = self.parse_declaration();
+        let errors = self.errors.len() - errors_before;
+        if errors > 0 {
+            self.error_counts.insert(statement.id(), errors);
+        }
+        statement
+    }
+
+    /// Parse a declaration, see [`Parser::declaration`] which counts its errors
+    fn parse_declaration(&mut self) -> Statement<'a> {
+        let start_index = self.current_index;
         let result = if self.advance_if_token_type_matches(&[TokenType::Var]) {
             self.var_decl()
         } else {
             self.statement()
         };
-        result.map_err(|err| {
+        result.unwrap_or_else(|err| {
+            self.errors.push(err);
             // synchronize the internal state to prepare the possible next call to `declaration()`
             self.synchronize();
-            err
+            let start = self.tokens[start_index].span;
+            let span = if self.current_index > start_index {
+                start.to(self.previous().span)
+            } else {
+                start
+            };
+            Statement::new_error_statement(span)
         })
     }
 
     /// Defines the rule to parse the declaration rule in the grammar:
     /// var_decl              → "var" IDENTIFIER ( "=" expression )? ";" ;
-    fn var_decl(&mut self) -> Result<Statement, ParserError> {
+    fn var_decl(&mut self) -> Result<Statement<'a>, ParserError> {
+        let var = self.previous().clone();
         let name = self.consume(TokenType::Identifier, "Expected variable name".into())?;
 
         let initializer = if self.advance_if_token_type_matches(&[TokenType::Equal]) {
@@ -92,17 +177,21 @@ impl Parser {
             None
         };
 
-        self.consume(
+        let semicolon = self.consume(
             TokenType::Semicolon,
             "Expected ';' after variable delcaration".into(),
         )?;
 
-        Ok(Statement::new_var_statement(name, initializer))
+        Ok(Statement::new_var_statement(
+            name,
+            initializer,
+            var.span.to(semicolon.span),
+        ))
     }
 
     /// Defines the rule to parse the statement rule in the grammar:
     /// statement             → expression_statement | print_statement | block ;
-    fn statement(&mut self) -> Result<Statement, ParserError> {
+    fn statement(&mut self) -> Result<Statement<'a>, ParserError> {
         if self.advance_if_token_type_matches(&[TokenType::Print]) {
             self.print_statement()
         } else if self.advance_if_token_type_matches(&[TokenType::LeftBrace]) {
@@ -114,120 +203,80 @@ impl Parser {
 
     /// Defines the rule to parse the print_statement rule in the grammar:
     /// print_statement       → print expression  ";" ;
-    fn print_statement(&mut self) -> Result<Statement, ParserError> {
+    fn print_statement(&mut self) -> Result<Statement<'a>, ParserError> {
+        let print = self.previous().clone();
         let expr = self.expression()?;
-        self.consume(TokenType::Semicolon, "Expect ';' after value.".into())?;
-        Ok(Statement::new_print_statement(expr))
+        let semicolon = self.consume(TokenType::Semicolon, "Expect ';' after value.".into())?;
+        Ok(Statement::new_print_statement(
+            expr,
+            print.span.to(semicolon.span),
+        ))
     }
 
     /// Defines the rule to parse the expression_statement rule in the grammar:
     /// expression_statement  → expression ";" ;
-    fn expression_statement(&mut self) -> Result<Statement, ParserError> {
+    fn expression_statement(&mut self) -> Result<Statement<'a>, ParserError> {
         let expr = self.expression()?;
-        self.consume(TokenType::Semicolon, "Expect ';' after expression.".into())?;
-        Ok(Statement::new_expression_statement(expr))
+        let semicolon =
+            self.consume(TokenType::Semicolon, "Expect ';' after expression.".into())?;
+        let span = expr.span().to(semicolon.span);
+        Ok(Statement::new_expression_statement(expr, span))
     }
 
     /// Defines the rule to parse the block rule in the grammar:
     /// block  → "{" declaration* "}" ;
-    fn block(&mut self) -> Result<Statement, ParserError> {
+    ///
+    /// An unclosed block is kept, up to the end of the source
+    fn block(&mut self) -> Result<Statement<'a>, ParserError> {
+        let left_brace = self.previous().clone();
         let mut statements = Vec::new();
+        self.block_depth += 1;
         while !self.check(TokenType::RightBrace) && self.peek().token_type != TokenType::Eof {
-            statements.push(self.declaration()?);
+            statements.push(self.declaration());
         }
-        self.consume(TokenType::RightBrace, "Expect '}' after block".to_owned())?;
-        Ok(Statement::new_block_statement(statements))
+        self.block_depth -= 1;
+        let end = match self.consume(TokenType::RightBrace, "Expect '}' after block".to_owned()) {
+            Ok(right_brace) => right_brace.span,
+            Err(err) => {
+                self.errors.push(err);
+                self.previous().span
+            }
+        };
+        Ok(Statement::new_block_statement(
+            statements,
+            left_brace.span.to(end),
+        ))
     }
 
     /// Defines the rule to parse the expression rule in the grammar:
-    /// expression     → assignment ;
-    fn expression(&mut self) -> Result<Expr, ParserError> {
-        self.assignment()
+    /// expression     → prefix ( INFIX_OPERATOR expression )* ;
+    fn expression(&mut self) -> Result<Expr<'a>, ParserError> {
+        self.parse_precedence(Precedence::Assignment)
     }
 
-    /// Defines the rule to parse the assignment rule in the grammar:
-    /// assignment     → IDENTIFIER "=" assignment | equality ;
-    fn assignment(&mut self) -> Result<Expr, ParserError> {
-        let expr = self.equality()?;
+    /// Parse an expression whose infix operators all bind at least as tightly as `min_precedence`
+    fn parse_precedence(&mut self, min_precedence: Precedence) -> Result<Expr<'a>, ParserError> {
+        let mut expr = self.prefix()?;
 
-        if self.advance_if_token_type_matches(&[TokenType::Equal]) {
-            let equals = self.previous().clone();
-            let value = self.assignment()?; // assignment is right-associative so we call it again here
-            if let Expr::Variable(v) = expr {
-                return Ok(Expr::new_assign(v.name, value));
-            } else {
-                return Err(ParserError::new(equals, "Invalid assignment target".into()));
+        while let Some(operator) = InfixOperator::find(self.peek().token_type) {
+            if operator.precedence < min_precedence {
+                break;
             }
+            let op = self.advance().clone();
+            let right = self.parse_precedence(operator.right_precedence())?;
+            expr = (operator.build)(self.arena, expr, op, right)?;
         }
 
         Ok(expr)
     }
 
-    /// Defines the rule to parse the equality rule in the grammar:
-    /// equality       → comparison ( ( "!=" | "==" ) comparison )* ;
-    fn equality(&mut self) -> Result<Expr, ParserError> {
-        let mut expr = self.comparison()?;
-        while self.advance_if_token_type_matches(&[TokenType::BangEqual, TokenType::EqualEqual]) {
-            let op = self.remove_previous();
-            let right = self.comparison()?;
-            expr = Expr::new_binary(expr, op, right);
-        }
-
-        Ok(expr)
-    }
-
-    /// Defines the rule to parse the comparison rule in the grammar:
-    /// comparison     → term ( ( ">" | ">=" | "<" | "<=" ) term )* ;
-    fn comparison(&mut self) -> Result<Expr, ParserError> {
-        let mut expr = self.term()?;
-        while self.advance_if_token_type_matches(&[
-            TokenType::Greater,
-            TokenType::GreaterEqual,
-            TokenType::Less,
-            TokenType::LessEqual,
-        ]) {
-            let op = self.remove_previous();
-            let right = self.term()?;
-            expr = Expr::new_binary(expr, op, right);
-        }
-
-        Ok(expr)
-    }
-
-    /// Defines the rule to parse the term rule in the grammar:
-    /// term           → factor ( ( "-" | "+" ) factor )* ;
-    fn term(&mut self) -> Result<Expr, ParserError> {
-        let mut expr = self.factor()?;
-        while self.advance_if_token_type_matches(&[TokenType::Minus, TokenType::Plus]) {
-            let op = self.remove_previous();
-            let right = self.factor()?;
-            expr = Expr::new_binary(expr, op, right);
-        }
-
-        Ok(expr)
-    }
-
-    /// Defines the rule to parse the factor rule in the grammar:
-    /// factor         → unary ( ( "/" | "*" ) unary )* ;
-    fn factor(&mut self) -> Result<Expr, ParserError> {
-        let mut expr = self.unary()?;
-        while self.advance_if_token_type_matches(&[TokenType::Slash, TokenType::Star]) {
-            let op = self.remove_previous();
-            let right = self.unary()?;
-            expr = Expr::new_binary(expr, op, right);
-        }
-
-        Ok(expr)
-    }
-
-    /// Defines the rule to parse the unary rule in the grammar:
-    /// unary          → ( "!" | "-" ) unary
-    ///                | primary ;
-    fn unary(&mut self) -> Result<Expr, ParserError> {
-        if self.advance_if_token_type_matches(&[TokenType::Bang, TokenType::Minus]) {
-            let op = self.remove_previous();
-            let right = self.unary()?;
-            return Ok(Expr::new_unary(op, right));
+    /// Defines the rule to parse the prefix rule in the grammar:
+    /// prefix         → PREFIX_OPERATOR prefix | primary ;
+    fn prefix(&mut self) -> Result<Expr<'a>, ParserError> {
+        if let Some(operator) = PrefixOperator::find(self.peek().token_type) {
+            let op = self.advance().clone();
+            let right = self.parse_precedence(operator.precedence)?;
+            return (operator.build)(self.arena, op, right);
         }
 
         self.primary()
@@ -236,24 +285,29 @@ impl Parser {
     /// Defines the rule to parse the primary rule in the grammar:
     /// primary        → NUMBER | STRING | "true" | "false" | "nil"
     ///                | "(" expression ")" | IDENTIFIER ;
-    fn primary(&mut self) -> Result<Expr, ParserError> {
+    fn primary(&mut self) -> Result<Expr<'a>, ParserError> {
         if self.advance_if_token_type_matches(&[TokenType::False, TokenType::True]) {
+            let token = self.previous().clone();
             return Ok(Expr::new_boolean_literal(
-                self.remove_previous().token_type == TokenType::True,
+                token.token_type == TokenType::True,
+                token.span,
             ));
         }
         if self.advance_if_token_type_matches(&[TokenType::Nil]) {
-            return Ok(Expr::new_nil_literal());
+            return Ok(Expr::new_nil_literal(self.previous().span));
         }
         if self.advance_if_token_type_matches(&[TokenType::String]) {
-            return Ok(Expr::new_string_literal(self.remove_previous().lexeme));
+            let token = self.previous().clone();
+            return Ok(Expr::new_string_literal(token.lexeme, token.span));
         }
         if self.advance_if_token_type_matches(&[TokenType::Number]) {
+            let token = self.previous().clone();
             return Ok(Expr::new_number_literal(
-                self.remove_previous()
+                token
                     .lexeme
                     .parse::<f64>()
                     .expect("Token should contain valid number after scanning is done."),
+                token.span,
             ));
         }
         if self.advance_if_token_type_matches(&[TokenType::Identifier]) {
@@ -261,9 +315,7 @@ impl Parser {
         }
 
         if self.advance_if_token_type_matches(std::slice::from_ref(&TokenType::LeftParen)) {
-            let expr = self.expression()?;
-            self.consume(TokenType::RightParen, "Expect ')' after expression.".into())?;
-            return Ok(Expr::new_grouping(expr));
+            return self.grouping();
         }
 
         Err(ParserError::new(
@@ -272,8 +324,81 @@ impl Parser {
         ))
     }
 
+    /// Parse the content of parentheses and the closing one, the opening one being consumed.
+    ///
+    /// When the content fails to parse but the parentheses are closed before the end of the
+    /// statement, the content is replaced by an error node and parsing goes on after them.
+    fn grouping(&mut self) -> Result<Expr<'a>, ParserError> {
+        let left_paren = self.previous().span;
+        let start_index = self.current_index;
+        let result = self.expression().and_then(|expr| {
+            let right_paren =
+                self.consume(TokenType::RightParen, "Expect ')' after expression.".into())?;
+            Ok(Expr::new_grouping(
+                self.arena,
+                expr,
+                left_paren.to(right_paren.span),
+            ))
+        });
+
+        result.or_else(|err| {
+            let Some(end_index) = self.closing_paren(start_index) else {
+                return Err(err);
+            };
+            self.errors.push(err);
+            let right_paren = self.tokens[end_index].span;
+            let content = if end_index > start_index {
+                self.tokens[start_index]
+                    .span
+                    .to(self.tokens[end_index - 1].span)
+            } else {
+                Span::new(
+                    right_paren.start,
+                    right_paren.start,
+                    right_paren.start_line,
+                    right_paren.start_line,
+                )
+            };
+            self.current_index = end_index + 1;
+            Ok(Expr::new_grouping(
+                self.arena,
+                Expr::new_error(content),
+                left_paren.to(right_paren),
+            ))
+        })
+    }
+
     // Helpers
 
+    /// Index of the `)` closing the parenthesis opened right before the token at `start_index`,
+    /// unless the statement ends first: a statement never depends on the tokens following its
+    /// next one, which incremental reparsing relies on
+    fn closing_paren(&self, start_index: usize) -> Option<usize> {
+        let mut depth = 0;
+        for (index, token) in self.tokens.iter().enumerate().skip(start_index) {
+            match token.token_type {
+                TokenType::LeftParen => depth += 1,
+                TokenType::RightParen if depth == 0 => return Some(index),
+                TokenType::RightParen => depth -= 1,
+                TokenType::Semicolon
+                | TokenType::LeftBrace
+                | TokenType::RightBrace
+                | TokenType::Eof => return None,
+                // where `synchronize` would stop
+                TokenType::Class
+                | TokenType::For
+                | TokenType::Fun
+                | TokenType::If
+                | TokenType::Print
+                | TokenType::Return
+                | TokenType::Var
+                | TokenType::While => return None,
+                _ => (),
+            }
+        }
+        None
+    }
+
     fn advance_if_token_type_matches(&mut self, token_types: &[TokenType]) -> bool {
         let token_type = self.peek().token_type;
         if token_types.contains(&token_type) {
@@ -316,20 +441,21 @@ impl Parser {
         self.tokens.get(self.current_index - 1).unwrap()
     }
 
-    fn remove_previous(&mut self) -> Token {
-        self.current_index -= 1;
-        self.tokens.remove(self.current_index)
-    }
-
-    #[allow(unused)]
-    /// Will be used later on once we add statements to the grammar
+    /// Skip tokens up to the probable start of the next statement, after an error.
+    ///
+    /// Inside a block, the closing brace is never skipped so that the block can be closed.
     fn synchronize(&mut self) {
+        let in_block = self.block_depth > 0;
+        if in_block && self.check(TokenType::RightBrace) {
+            return;
+        }
         self.advance();
         while self.peek().token_type != TokenType::Eof {
             if self.previous().token_type == TokenType::Semicolon {
                 return;
             }
             match self.peek().token_type {
+                TokenType::RightBrace if in_block => return,
                 TokenType::Class
                 | TokenType::For
                 | TokenType::Fun
@@ -346,3 +472,259 @@ impl Parser {
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::Parser;
+    use crate::{
+        ast::{Arena, Expr, Statement},
+        scanner::Scanner,
+        token::Span,
+    };
+
+    fn parse<'a>(source: &str, arena: &'a Arena<'a>) -> Vec<Statement<'a>> {
+        Parser::new(Scanner::new(source).scan_tokens().unwrap(), arena)
+            .parse()
+            .unwrap()
+    }
+
+    /// Render the expression of an expression statement with every operation parenthesized
+    fn grouped(source: &str) -> String {
+        fn render(expr: &Expr) -> String {
+            match expr {
+                Expr::Unary(v) => format!("({}{})", v.op.lexeme, render(v.expr)),
+                Expr::Binary(v) => {
+                    format!("({} {} {})", render(v.left), v.op.lexeme, render(v.right))
+                }
+                Expr::Grouping(v) => format!("[{}]", render(v.expr)),
+                Expr::Literal(_) => "lit".to_owned(),
+                Expr::Variable(v) => v.name.lexeme.to_string(),
+                Expr::Assign(v) => format!("({} = {})", v.name.lexeme, render(v.value)),
+                Expr::Error(_) => "error".to_owned(),
+            }
+        }
+        let arena = Arena::new();
+        let Statement::Expression(statement) = &parse(source, &arena)[0] else {
+            panic!("expected an expression statement")
+        };
+        render(&statement.expr)
+    }
+
+    fn errors(source: &str) -> Vec<String> {
+        Parser::new(Scanner::new(source).scan_tokens().unwrap(), &Arena::new())
+            .parse()
+            .unwrap_err()
+            .iter()
+            .map(|e| e.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_precedence() {
+        assert_eq!(
+            grouped("a + b * c == d - e / f;"),
+            "((a + (b * c)) == (d - (e / f)))"
+        );
+        assert_eq!(
+            grouped("a < b == c >= d != e;"),
+            "(((a < b) == (c >= d)) != e)"
+        );
+        assert_eq!(grouped("-a * !b - --c;"), "(((-a) * (!b)) - (-(-c)))");
+        assert_eq!(grouped("(a + b) * c;"), "([(a + b)] * c)");
+        assert_eq!(grouped("a = b == c;"), "(a = (b == c))");
+    }
+
+    #[test]
+    fn test_associativity() {
+        assert_eq!(grouped("a - b - c;"), "((a - b) - c)");
+        assert_eq!(grouped("a / b * c;"), "((a / b) * c)");
+        assert_eq!(grouped("a == b != c;"), "((a == b) != c)");
+        assert_eq!(grouped("a = b = c + 1;"), "(a = (b = (c + lit)))");
+    }
+
+    #[test]
+    fn test_expression_errors() {
+        assert_eq!(
+            errors("a + b = c;"),
+            ["Parsing Error - line 0 at =: Invalid assignment target"]
+        );
+        assert_eq!(
+            errors("-a = 1; (a) = 2;"),
+            [
+                "Parsing Error - line 0 at =: Invalid assignment target",
+                "Parsing Error - line 0 at =: Invalid assignment target"
+            ]
+        );
+        assert_eq!(
+            errors("print 1 +;"),
+            ["Parsing Error - line 0 at ;: Expected expression"]
+        );
+        assert_eq!(
+            errors("print (1;"),
+            ["Parsing Error - line 0 at ;: Expect ')' after expression."]
+        );
+    }
+
+    #[test]
+    fn test_spans() {
+        let arena = Arena::new();
+        let statements = parse("print (1 +\n 2) * -a;\n{ b = 3; }", &arena);
+
+        let Statement::Print(print) = &statements[0] else {
+            panic!("expected a print statement")
+        };
+        assert_eq!(print.span, Span::new(0, 20, 0, 1));
+        let Expr::Binary(product) = &print.expr else {
+            panic!("expected a binary expression")
+        };
+        assert_eq!(product.span, Span::new(6, 19, 0, 1));
+        assert_eq!(product.left.span(), Span::new(6, 14, 0, 1));
+        assert_eq!(product.right.span(), Span::new(17, 19, 1, 1));
+
+        let Statement::Block(block) = &statements[1] else {
+            panic!("expected a block statement")
+        };
+        assert_eq!(block.span, Span::new(21, 31, 2, 2));
+        assert_eq!(block.statements[0].span(), Span::new(23, 29, 2, 2));
+    }
+
+    fn parse_partial<'a>(source: &str, arena: &'a Arena<'a>) -> (Vec<Statement<'a>>, Vec<String>) {
+        let (statements, errors) =
+            Parser::new(Scanner::new(source).scan_tokens().unwrap(), arena).parse_partial();
+        (statements, errors.iter().map(|e| e.to_string()).collect())
+    }
+
+    #[test]
+    fn test_statement_recovery() {
+        let arena = Arena::new();
+        let (statements, errors) = parse_partial("print 1 +; var = 2; print 3;", &arena);
+        assert_eq!(
+            errors,
+            [
+                "Parsing Error - line 0 at ;: Expected expression",
+                "Parsing Error - line 0 at =: Expected variable name"
+            ]
+        );
+        assert_eq!(statements.len(), 3);
+        assert!(matches!(statements[0], Statement::Error(_)));
+        assert_eq!(statements[0].span(), Span::new(0, 10, 0, 0));
+        assert!(matches!(statements[1], Statement::Error(_)));
+        assert_eq!(statements[1].span(), Span::new(11, 19, 0, 0));
+        assert!(matches!(statements[2], Statement::Print(_)));
+    }
+
+    #[test]
+    fn test_block_recovery() {
+        let arena = Arena::new();
+        let (statements, errors) =
+            parse_partial("{ print 1 } print 2; { var a = 1; a +; print a;", &arena);
+        assert_eq!(
+            errors,
+            [
+                "Parsing Error - line 0 at }: Expect ';' after value.",
+                "Parsing Error - line 0 at ;: Expected expression",
+                "Parsing Error - line 0 at end: Expect '}' after block"
+            ]
+        );
+        assert_eq!(statements.len(), 3);
+        let Statement::Block(block) = &statements[0] else {
+            panic!("expected a block statement")
+        };
+        assert_eq!(block.span, Span::new(0, 11, 0, 0));
+        assert!(matches!(block.statements[..], [Statement::Error(_)]));
+        assert!(matches!(statements[1], Statement::Print(_)));
+
+        // the unclosed block is kept up to the end of the source
+        let Statement::Block(block) = &statements[2] else {
+            panic!("expected a block statement")
+        };
+        assert_eq!(block.span, Span::new(21, 47, 0, 0));
+        assert!(matches!(
+            block.statements[..],
+            [
+                Statement::Variable(_),
+                Statement::Error(_),
+                Statement::Print(_)
+            ]
+        ));
+    }
+
+    #[test]
+    fn test_grouping_recovery() {
+        let arena = Arena::new();
+        let (statements, errors) = parse_partial("(1 +) * ((a b) - ());", &arena);
+        assert_eq!(
+            errors,
+            [
+                "Parsing Error - line 0 at ): Expected expression",
+                "Parsing Error - line 0 at b: Expect ')' after expression.",
+                "Parsing Error - line 0 at ): Expected expression"
+            ]
+        );
+        let Statement::Expression(statement) = &statements[0] else {
+            panic!("expected an expression statement")
+        };
+        let Expr::Binary(product) = &statement.expr else {
+            panic!("expected a binary expression")
+        };
+        let Expr::Grouping(left) = product.left else {
+            panic!("expected a grouping")
+        };
+        assert!(matches!(*left.expr, Expr::Error(_)));
+        assert_eq!(left.expr.span(), Span::new(1, 4, 0, 0));
+        let Expr::Grouping(right) = product.right else {
+            panic!("expected a grouping")
+        };
+        let Expr::Binary(difference) = right.expr else {
+            panic!("expected a binary expression")
+        };
+        assert_eq!(difference.left.span(), Span::new(9, 14, 0, 0));
+        assert_eq!(difference.right.span(), Span::new(17, 19, 0, 0));
+        let Expr::Grouping(empty) = difference.right else {
+            panic!("expected a grouping")
+        };
+        assert_eq!(empty.expr.span(), Span::new(18, 18, 0, 0));
+
+        // parentheses left open end the statement
+        let (statements, errors) = parse_partial("print (1 + ;", &arena);
+        assert_eq!(errors, ["Parsing Error - line 0 at ;: Expected expression"]);
+        assert!(matches!(statements[..], [Statement::Error(_)]));
+
+        // as do the keywords starting the next one
+        let (statements, errors) = parse_partial("x = (1 2 print 3);", &arena);
+        assert_eq!(
+            errors,
+            [
+                "Parsing Error - line 0 at 2: Expect ')' after expression.",
+                "Parsing Error - line 0 at ): Expect ';' after value."
+            ]
+        );
+        assert!(matches!(
+            statements[..],
+            [Statement::Error(_), Statement::Error(_)]
+        ));
+        assert_eq!(statements[0].span(), Span::new(0, 8, 0, 0));
+    }
+
+    #[test]
+    fn test_ids_are_unique() {
+        let arena = Arena::new();
+        let statements = parse("var a = 1 + 2; { a = -a; }", &arena);
+        let Statement::Variable(var) = &statements[0] else {
+            panic!("expected a variable statement")
+        };
+        let Some(Expr::Binary(sum)) = &var.initializer else {
+            panic!("expected a binary expression")
+        };
+        let mut ids = vec![
+            var.id,
+            sum.id,
+            sum.left.id(),
+            sum.right.id(),
+            statements[1].id(),
+        ];
+        ids.sort();
+        ids.dedup();
+        assert_eq!(ids.len(), 5);
+    }
+}
@@ -0,0 +1,200 @@
+use super::error::ParserError;
+use crate::{
+    ast::{Arena, Expr},
+    token::{Token, TokenType},
+};
+
+/// How tightly an operator binds its operands, from the loosest to the tightest
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy)]
+pub enum Precedence {
+    Assignment,
+    Equality,
+    Comparison,
+    Term,
+    Factor,
+    Unary,
+}
+
+impl Precedence {
+    /// The precedence binding just tighter than `self`
+    fn next(self) -> Self {
+        match self {
+            Precedence::Assignment => Precedence::Equality,
+            Precedence::Equality => Precedence::Comparison,
+            Precedence::Comparison => Precedence::Term,
+            Precedence::Term => Precedence::Factor,
+            Precedence::Factor | Precedence::Unary => Precedence::Unary,
+        }
+    }
+}
+
+/// Which side a chain of operators of the same precedence groups on
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Associativity {
+    /// `a - b - c` is `(a - b) - c`
+    Left,
+    /// `a = b = c` is `a = (b = c)`
+    Right,
+}
+
+/// Operator written between its two operands
+pub struct InfixOperator {
+    pub token_type: TokenType,
+    pub precedence: Precedence,
+    pub associativity: Associativity,
+    /// Builds the node from the left operand, the operator and the right operand
+    pub build:
+        for<'a> fn(&'a Arena<'a>, Expr<'a>, Token, Expr<'a>) -> Result<Expr<'a>, ParserError>,
+}
+
+impl InfixOperator {
+    pub fn find(token_type: TokenType) -> Option<&'static InfixOperator> {
+        INFIX_OPERATORS.iter().find(|o| o.token_type == token_type)
+    }
+
+    /// Minimum precedence of the operators of the right operand
+    pub fn right_precedence(&self) -> Precedence {
+        match self.associativity {
+            Associativity::Left => self.precedence.next(),
+            Associativity::Right => self.precedence,
+        }
+    }
+}
+
+/// Operator written before its only operand
+pub struct PrefixOperator {
+    pub token_type: TokenType,
+    /// Minimum precedence of the operators of the operand
+    pub precedence: Precedence,
+    pub build: for<'a> fn(&'a Arena<'a>, Token, Expr<'a>) -> Result<Expr<'a>, ParserError>,
+}
+
+impl PrefixOperator {
+    pub fn find(token_type: TokenType) -> Option<&'static PrefixOperator> {
+        PREFIX_OPERATORS.iter().find(|o| o.token_type == token_type)
+    }
+}
+
+const fn infix(
+    token_type: TokenType,
+    precedence: Precedence,
+    associativity: Associativity,
+    build: for<'a> fn(&'a Arena<'a>, Expr<'a>, Token, Expr<'a>) -> Result<Expr<'a>, ParserError>,
+) -> InfixOperator {
+    InfixOperator {
+        token_type,
+        precedence,
+        associativity,
+        build,
+    }
+}
+
+/// Every infix operator of the grammar
+pub const INFIX_OPERATORS: &[InfixOperator] = &[
+    infix(
+        TokenType::Equal,
+        Precedence::Assignment,
+        Associativity::Right,
+        assign,
+    ),
+    infix(
+        TokenType::BangEqual,
+        Precedence::Equality,
+        Associativity::Left,
+        binary,
+    ),
+    infix(
+        TokenType::EqualEqual,
+        Precedence::Equality,
+        Associativity::Left,
+        binary,
+    ),
+    infix(
+        TokenType::Greater,
+        Precedence::Comparison,
+        Associativity::Left,
+        binary,
+    ),
+    infix(
+        TokenType::GreaterEqual,
+        Precedence::Comparison,
+        Associativity::Left,
+        binary,
+    ),
+    infix(
+        TokenType::Less,
+        Precedence::Comparison,
+        Associativity::Left,
+        binary,
+    ),
+    infix(
+        TokenType::LessEqual,
+        Precedence::Comparison,
+        Associativity::Left,
+        binary,
+    ),
+    infix(
+        TokenType::Minus,
+        Precedence::Term,
+        Associativity::Left,
+        binary,
+    ),
+    infix(
+        TokenType::Plus,
+        Precedence::Term,
+        Associativity::Left,
+        binary,
+    ),
+    infix(
+        TokenType::Slash,
+        Precedence::Factor,
+        Associativity::Left,
+        binary,
+    ),
+    infix(
+        TokenType::Star,
+        Precedence::Factor,
+        Associativity::Left,
+        binary,
+    ),
+];
+
+/// Every prefix operator of the grammar
+pub const PREFIX_OPERATORS: &[PrefixOperator] = &[
+    PrefixOperator {
+        token_type: TokenType::Bang,
+        precedence: Precedence::Unary,
+        build: unary,
+    },
+    PrefixOperator {
+        token_type: TokenType::Minus,
+        precedence: Precedence::Unary,
+        build: unary,
+    },
+];
+
+fn binary<'a>(
+    arena: &'a Arena<'a>,
+    left: Expr<'a>,
+    op: Token,
+    right: Expr<'a>,
+) -> Result<Expr<'a>, ParserError> {
+    Ok(Expr::new_binary(arena, left, op, right))
+}
+
+fn unary<'a>(arena: &'a Arena<'a>, op: Token, expr: Expr<'a>) -> Result<Expr<'a>, ParserError> {
+    Ok(Expr::new_unary(arena, op, expr))
+}
+
+/// Only variables can be assigned, the error is reported at the `=`
+fn assign<'a>(
+    arena: &'a Arena<'a>,
+    target: Expr<'a>,
+    equals: Token,
+    value: Expr<'a>,
+) -> Result<Expr<'a>, ParserError> {
+    match target {
+        Expr::Variable(v) => Ok(Expr::new_assign(arena, v.name, value)),
+        _ => Err(ParserError::new(equals, "Invalid assignment target".into())),
+    }
+}
@@ -0,0 +1,128 @@
+use std::str::FromStr;
+
+use camino::Utf8PathBuf;
+use thiserror::Error;
+
+/// Commands of the REPL, typed at an empty prompt after a colon
+#[derive(Debug, PartialEq, Eq)]
+pub enum Command {
+    Help,
+    /// Print the variables of the session with their values
+    Env,
+    /// Print the tokens scanned from the code
+    Tokens(String),
+    /// Print the AST parsed from the code
+    Ast(String),
+    /// Run a file in the session
+    Load(Utf8PathBuf),
+    /// Start over with a fresh interpreter, dropping every variable
+    Reset,
+    /// Run the code and print how long it took
+    Time(String),
+    /// Write the inputs of the session which ran successfully to a file
+    Save(Utf8PathBuf),
+}
+
+impl Command {
+    /// Names of the commands, sorted
+    pub const NAMES: &'static [&'static str] = &[
+        ":ast", ":env", ":help", ":load", ":reset", ":save", ":time", ":tokens",
+    ];
+
+    pub const HELP: &'static str = "\
+:help           show this help
+:env            print the variables and their values
+:tokens <code>  print the tokens of the code
+:ast <code>     print the AST of the code
+:load <file>    run a file in the session
+:reset          drop every variable
+:time <code>    run the code and print how long it took
+:save <file>    write the inputs which ran successfully to a file
+exit            leave the REPL";
+}
+
+impl FromStr for Command {
+    type Err = CommandError;
+
+    fn from_str(line: &str) -> Result<Self, Self::Err> {
+        let line = line.trim();
+        let (name, argument) = match line.split_once(char::is_whitespace) {
+            Some((name, argument)) => (name, argument.trim()),
+            None => (line, ""),
+        };
+        let with_argument = |what: &str| {
+            if argument.is_empty() {
+                Err(CommandError::new(format!("{name} expects {what}")))
+            } else {
+                Ok(argument.to_string())
+            }
+        };
+        let without_argument = |command| {
+            if argument.is_empty() {
+                Ok(command)
+            } else {
+                Err(CommandError::new(format!("{name} expects no argument")))
+            }
+        };
+
+        match name {
+            ":help" => without_argument(Command::Help),
+            ":env" => without_argument(Command::Env),
+            ":tokens" => with_argument("some code").map(Command::Tokens),
+            ":ast" => with_argument("some code").map(Command::Ast),
+            ":load" => with_argument("a file").map(|path| Command::Load(path.into())),
+            ":reset" => without_argument(Command::Reset),
+            ":time" => with_argument("some code").map(Command::Time),
+            ":save" => with_argument("a file").map(|path| Command::Save(path.into())),
+            _ => Err(CommandError::new(format!(
+                "Unknown command {name}, see :help"
+            ))),
+        }
+    }
+}
+
+#[derive(Error, Debug, PartialEq, Eq)]
+#[error("Command Error - {msg}")]
+pub struct CommandError {
+    msg: String,
+}
+
+impl CommandError {
+    pub fn new(msg: String) -> Self {
+        Self { msg }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Command, CommandError};
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(":help".parse(), Ok(Command::Help));
+        assert_eq!(" :env ".parse(), Ok(Command::Env));
+        assert_eq!(
+            ":tokens  var a = 1;".parse(),
+            Ok(Command::Tokens("var a = 1;".to_string()))
+        );
+        assert_eq!(
+            ":load dir/file.rox".parse(),
+            Ok(Command::Load("dir/file.rox".into()))
+        );
+
+        assert_eq!(
+            ":ast".parse::<Command>(),
+            Err(CommandError::new(":ast expects some code".to_string()))
+        );
+        assert_eq!(
+            ":reset now".parse::<Command>(),
+            Err(CommandError::new(":reset expects no argument".to_string()))
+        );
+        assert_eq!(
+            ":quit".parse::<Command>(),
+            Err(CommandError::new(
+                "Unknown command :quit, see :help".to_string()
+            ))
+        );
+    }
+}
@@ -0,0 +1,349 @@
+use std::{borrow::Cow, ops::Range, rc::Rc};
+
+use rustyline::{
+    completion::Completer,
+    highlight::Highlighter,
+    hint::Hinter,
+    validate::Validator,
+    Context,
+    Helper,
+};
+
+use super::command::Command;
+use crate::{
+    scanner::{Scanner, KEYWORDS},
+    token::TokenType,
+};
+
+/// Completes and highlights the lines edited in the REPL
+#[derive(Default)]
+pub struct RoxHelper {
+    /// Names of the variables defined in the session
+    names: Vec<Rc<str>>,
+}
+
+impl RoxHelper {
+    pub fn set_names(&mut self, names: Vec<Rc<str>>) {
+        self.names = names;
+    }
+
+    /// Start of the word ending at `pos` in `line` and the sorted completions of that word: the
+    /// commands at the start of the line, otherwise the keywords and the names of the variables.
+    /// There's nothing to complete in a string or a comment.
+    fn completions(&self, line: &str, pos: usize) -> (usize, Vec<String>) {
+        let before = &line[..pos];
+        let start = before
+            .char_indices()
+            .rev()
+            .find(|(_, c)| !(c.is_ascii_alphanumeric() || *c == '_'))
+            .map_or(0, |(i, c)| i + c.len_utf8());
+        let word = &before[start..];
+
+        let candidates: Vec<&str> = if before[..start].ends_with(':') {
+            Command::NAMES
+                .iter()
+                .map(|name| &name[1..])
+                .filter(|name| start == 1 && name.starts_with(word))
+                .collect()
+        } else if word.is_empty()
+            || word.starts_with(|c: char| c.is_ascii_digit())
+            || in_string_or_comment(before)
+        {
+            Vec::new()
+        } else {
+            KEYWORDS
+                .keys()
+                .copied()
+                .chain(self.names.iter().map(|name| &**name))
+                .filter(|name| name.starts_with(word))
+                .collect()
+        };
+
+        let mut candidates: Vec<String> = candidates.into_iter().map(String::from).collect();
+        candidates.sort_unstable();
+        candidates.dedup();
+        (start, candidates)
+    }
+}
+
+/// Whether the end of `line` is in a string or a comment
+fn in_string_or_comment(line: &str) -> bool {
+    let mut in_string = false;
+    for (i, c) in line.char_indices() {
+        match c {
+            '"' => in_string = !in_string,
+            '/' if !in_string && line[i + 1..].starts_with('/') => return true,
+            _ => (),
+        }
+    }
+    in_string
+}
+
+impl Completer for RoxHelper {
+    type Candidate = String;
+
+    fn complete(
+        &self,
+        line: &str,
+        pos: usize,
+        _ctx: &Context<'_>,
+    ) -> rustyline::Result<(usize, Vec<String>)> {
+        Ok(self.completions(line, pos))
+    }
+}
+
+impl Hinter for RoxHelper {
+    type Hint = String;
+}
+
+/// Styles of the highlighted parts of a line, as ANSI escape sequences
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+enum Style {
+    Keyword,
+    String,
+    Number,
+    Comment,
+    Operator,
+    /// Lexeme the scanner fails on
+    Error,
+    /// Bracket matching the one under the cursor
+    Bracket,
+}
+
+impl Style {
+    fn escape(self) -> &'static str {
+        match self {
+            Style::Keyword => "\x1b[1;35m",
+            Style::String => "\x1b[32m",
+            Style::Number => "\x1b[36m",
+            Style::Comment => "\x1b[90m",
+            Style::Operator => "\x1b[33m",
+            Style::Error => "\x1b[4;31m",
+            Style::Bracket => "\x1b[1;7m",
+        }
+    }
+}
+
+/// Styled ranges of `line`, in order, from the lexemes the scanner finds in it. The cursor is at
+/// `pos`, before the character under it.
+fn styles(line: &str, pos: usize) -> Vec<(Range<usize>, Style)> {
+    let mut styles = Vec::new();
+    // ranges and types of the brackets, to match them
+    let mut brackets = Vec::new();
+    let mut scanner = Scanner::new(line);
+    loop {
+        let (start, _) = scanner.position();
+        let Some(result) = scanner.scan_lexeme() else {
+            break;
+        };
+        let range = start..scanner.position().0;
+        let style = match result {
+            Ok(Some(token)) => match token.token_type {
+                TokenType::Identifier => continue,
+                TokenType::String => Style::String,
+                TokenType::Number => Style::Number,
+                _ if KEYWORDS.contains_key(&*token.lexeme) => Style::Keyword,
+                token_type @ (TokenType::LeftParen
+                | TokenType::RightParen
+                | TokenType::LeftBrace
+                | TokenType::RightBrace) => {
+                    brackets.push((range.clone(), token_type));
+                    Style::Operator
+                }
+                _ => Style::Operator,
+            },
+            // whitespace, a comment or a shebang
+            Ok(None) if line[range.clone()].starts_with(['/', '#']) => Style::Comment,
+            Ok(None) => continue,
+            Err(_) => Style::Error,
+        };
+        styles.push((range, style));
+    }
+
+    if let Some(matching) = matching_bracket(&brackets, pos) {
+        let index = styles
+            .iter()
+            .position(|(range, _)| *range == matching)
+            .expect("brackets are styled");
+        styles[index].1 = Style::Bracket;
+    }
+    styles
+}
+
+/// Range of the bracket matching the one under the cursor, or else the one just before it
+fn matching_bracket(brackets: &[(Range<usize>, TokenType)], pos: usize) -> Option<Range<usize>> {
+    let index = brackets
+        .iter()
+        .position(|(range, _)| range.start == pos)
+        .or_else(|| brackets.iter().position(|(range, _)| range.end == pos))?;
+
+    let (open, close, forward) = match brackets[index].1 {
+        TokenType::LeftParen => (TokenType::LeftParen, TokenType::RightParen, true),
+        TokenType::RightParen => (TokenType::LeftParen, TokenType::RightParen, false),
+        TokenType::LeftBrace => (TokenType::LeftBrace, TokenType::RightBrace, true),
+        _ => (TokenType::LeftBrace, TokenType::RightBrace, false),
+    };
+    let (nested, matching) = if forward {
+        (open, close)
+    } else {
+        (close, open)
+    };
+    let others: Box<dyn Iterator<Item = _>> = if forward {
+        Box::new(brackets[index + 1..].iter())
+    } else {
+        Box::new(brackets[..index].iter().rev())
+    };
+
+    let mut depth = 0;
+    for (range, token_type) in others {
+        if *token_type == nested {
+            depth += 1;
+        } else if *token_type == matching {
+            if depth == 0 {
+                return Some(range.clone());
+            }
+            depth -= 1;
+        }
+    }
+    None
+}
+
+impl Highlighter for RoxHelper {
+    fn highlight<'l>(&self, line: &'l str, pos: usize) -> Cow<'l, str> {
+        let styles = styles(line, pos);
+        if styles.is_empty() {
+            return Cow::Borrowed(line);
+        }
+
+        let mut highlighted = String::with_capacity(line.len() * 2);
+        let mut end = 0;
+        for (range, style) in styles {
+            highlighted.push_str(&line[end..range.start]);
+            highlighted.push_str(style.escape());
+            highlighted.push_str(&line[range.clone()]);
+            highlighted.push_str("\x1b[0m");
+            end = range.end;
+        }
+        highlighted.push_str(&line[end..]);
+        Cow::Owned(highlighted)
+    }
+
+    fn highlight_char(&self, _line: &str, _pos: usize) -> bool {
+        // the styles of the whole line can change with any character, and the matched bracket
+        // with any move of the cursor
+        true
+    }
+}
+
+impl Validator for RoxHelper {}
+
+impl Helper for RoxHelper {}
+
+#[cfg(test)]
+mod test {
+    use super::{styles, Command, RoxHelper, Style};
+
+    fn completions(line: &str) -> (usize, Vec<String>) {
+        let helper = RoxHelper {
+            names: vec!["format".into(), "value".into(), "var_count".into()],
+        };
+        helper.completions(line, line.len())
+    }
+
+    #[test]
+    fn test_completions() {
+        assert_eq!(completions("pr"), (0, vec!["print".to_string()]));
+        assert_eq!(
+            completions("print (1 + va"),
+            (
+                11,
+                vec![
+                    "value".to_string(),
+                    "var".to_string(),
+                    "var_count".to_string()
+                ]
+            )
+        );
+        assert_eq!(
+            completions("f"),
+            (
+                0,
+                vec![
+                    "false".to_string(),
+                    "for".to_string(),
+                    "format".to_string(),
+                    "fun".to_string()
+                ]
+            )
+        );
+        assert_eq!(completions(":re"), (1, vec!["reset".to_string()]));
+        assert_eq!(
+            completions(":"),
+            (
+                1,
+                Command::NAMES
+                    .iter()
+                    .map(|name| name[1..].to_string())
+                    .collect()
+            )
+        );
+
+        // commands only start a line
+        assert_eq!(completions("print :re"), (7, vec![]));
+        assert_eq!(completions(""), (0, vec![]));
+        assert_eq!(completions("1e"), (0, vec![]));
+        assert_eq!(completions("print \"va"), (7, vec![]));
+        assert_eq!(completions("print 1; // va"), (12, vec![]));
+        assert_eq!(completions("print \"a\" + va").1.len(), 3);
+
+        // the word can follow a character of several bytes
+        assert_eq!(completions("print \"\u{e9}"), (9, vec![]));
+        assert_eq!(completions("\u{e9}va"), (2, completions("va").1));
+    }
+
+    #[test]
+    fn test_styles() {
+        let line = "var a = (\"s\" + 1) and b; // done";
+        assert_eq!(
+            styles(line, 0),
+            vec![
+                (0..3, Style::Keyword),
+                (6..7, Style::Operator),
+                (8..9, Style::Operator),
+                (9..12, Style::String),
+                (13..14, Style::Operator),
+                (15..16, Style::Number),
+                (16..17, Style::Operator),
+                (18..21, Style::Keyword),
+                (23..24, Style::Operator),
+                (25..32, Style::Comment),
+            ]
+        );
+        assert_eq!(
+            styles("a @ \"b", 0),
+            vec![(2..3, Style::Error), (4..6, Style::Error)]
+        );
+    }
+
+    #[test]
+    fn test_matching_bracket() {
+        let bracket = |line: &str, pos: usize| {
+            styles(line, pos)
+                .into_iter()
+                .find(|(_, style)| *style == Style::Bracket)
+                .map(|(range, _)| range.start)
+        };
+        let line = "{ print (1 + (2)); \")\" }";
+        // under the cursor
+        assert_eq!(bracket(line, 0), Some(23));
+        assert_eq!(bracket(line, 8), Some(16));
+        assert_eq!(bracket(line, 16), Some(8));
+        // just before the cursor
+        assert_eq!(bracket(line, 24), Some(0));
+        assert_eq!(bracket(line, 14), Some(15));
+        assert_eq!(bracket(line, 3), None);
+        // in a string
+        assert_eq!(bracket(line, 20), None);
+        assert_eq!(bracket("(()", 0), None);
+    }
+}
@@ -0,0 +1,247 @@
+pub mod command;
+pub mod helper;
+
+use std::{
+    env,
+    io::{self, IsTerminal, Stdin, Write},
+    path::PathBuf,
+    rc::Rc,
+};
+
+use log::info;
+use rustyline::{error::ReadlineError, history::DefaultHistory, CompletionType, Config, Editor};
+
+use self::helper::RoxHelper;
+use crate::{
+    ast::{Arena, Statement},
+    error::FacingRoxResult,
+    parser::Parser,
+    scanner::Scanner,
+    token::TokenType,
+};
+
+/// What the user typed at a prompt
+#[derive(Debug, PartialEq, Eq)]
+pub enum Input {
+    /// A line, without its line ending
+    Line(String),
+    /// Ctrl-C was pressed instead of typing a line
+    Interrupted,
+    /// The input ended
+    Eof,
+}
+
+/// Reads the lines typed in the REPL.
+///
+/// When stdin is a terminal, lines are read through a line editor with the usual Emacs bindings:
+/// cursor movement, reverse search of the history with Ctrl-R, kill and yank. The history is
+/// loaded from and saved to a file so that it's kept between sessions. Lines are highlighted as
+/// they are typed, along with the bracket matching the one at the cursor. Tab completes keywords,
+/// variables and commands, listing the candidates when there are several. Otherwise, e.g. when a
+/// program is piped into the REPL, lines are read as they come.
+pub enum LineReader {
+    Editor {
+        editor: Editor<RoxHelper, DefaultHistory>,
+        /// File the history is saved to, not saved if `None`
+        history: Option<PathBuf>,
+    },
+    Plain(Stdin),
+}
+
+impl LineReader {
+    /// Build the reader fitting stdin, saving the history to `history` if set
+    pub fn new(history: Option<PathBuf>) -> FacingRoxResult<Self> {
+        if !io::stdin().is_terminal() {
+            return Ok(LineReader::Plain(io::stdin()));
+        }
+
+        let config = Config::builder()
+            .auto_add_history(false)
+            .history_ignore_dups(true)?
+            .completion_type(CompletionType::List)
+            .build();
+        let mut editor = Editor::with_config(config)?;
+        editor.set_helper(Some(RoxHelper::default()));
+        if let Some(path) = history.as_ref() {
+            // there's no history yet on the first session
+            if let Err(e) = editor.load_history(path) {
+                info!("cannot load the history from {}: {}", path.display(), e);
+            }
+        }
+        Ok(LineReader::Editor { editor, history })
+    }
+
+    /// `$ROX_HISTORY_FILE` if set, otherwise the `rox/history` file of the user's data directory
+    pub fn default_history() -> Option<PathBuf> {
+        if let Some(path) = env::var_os("ROX_HISTORY_FILE") {
+            return Some(PathBuf::from(path));
+        }
+        let data_home = env::var_os("XDG_DATA_HOME")
+            .map(PathBuf::from)
+            .or_else(|| {
+                env::var_os("HOME").map(|home| PathBuf::from(home).join(".local").join("share"))
+            })?;
+        Some(data_home.join("rox").join("history"))
+    }
+
+    /// Read the next line after showing `prompt`
+    pub fn read_line(&mut self, prompt: &str) -> FacingRoxResult<Input> {
+        match self {
+            LineReader::Editor { editor, .. } => match editor.readline(prompt) {
+                Ok(line) => Ok(Input::Line(line)),
+                Err(ReadlineError::Interrupted) => Ok(Input::Interrupted),
+                Err(ReadlineError::Eof) => Ok(Input::Eof),
+                Err(e) => Err(e.into()),
+            },
+            LineReader::Plain(stdin) => {
+                print!("{prompt}");
+                io::stdout().flush()?;
+
+                let mut line = String::new();
+                if stdin.read_line(&mut line)? == 0 {
+                    return Ok(Input::Eof);
+                }
+                let len = line.trim_end_matches(['\n', '\r']).len();
+                line.truncate(len);
+                Ok(Input::Line(line))
+            }
+        }
+    }
+
+    /// Set the names of the variables defined in the session, to complete them
+    pub fn set_names(&mut self, names: Vec<Rc<str>>) {
+        if let LineReader::Editor { editor, .. } = self {
+            if let Some(helper) = editor.helper_mut() {
+                helper.set_names(names);
+            }
+        }
+    }
+
+    /// Add a line to the history and save it, if the lines are edited
+    pub fn add_history(&mut self, line: &str) {
+        if let LineReader::Editor { editor, history } = self {
+            if line.trim().is_empty() || !editor.add_history_entry(line).unwrap_or(false) {
+                return;
+            }
+            let Some(path) = history.as_ref() else {
+                return;
+            };
+            // the REPL is still usable without a saved history
+            if let Some(parent) = path.parent() {
+                let _ = std::fs::create_dir_all(parent);
+            }
+            if let Err(e) = editor.append_history(path) {
+                info!("cannot save the history to {}: {}", path.display(), e);
+            }
+        }
+    }
+}
+
+/// A parsed entry of the REPL
+pub struct Entry<'a> {
+    /// Source of the entry, with the `;` it left out if any, so that it can run from a file
+    pub source: String,
+    pub statements: Vec<Statement<'a>>,
+}
+
+/// Parse an entry of the REPL, which can leave out the `;` ending a single expression statement,
+/// allocating its expressions in `arena`. Returns `None` if it fails to scan or parse.
+pub fn parse_entry<'a>(source: &str, arena: &'a Arena<'a>) -> Option<Entry<'a>> {
+    let parse = |source: String| {
+        let tokens = Scanner::new(&source).scan_tokens().ok()?;
+        let statements = Parser::new(tokens, arena).parse().ok()?;
+        Some(Entry { source, statements })
+    };
+
+    parse(source.to_string()).or_else(|| {
+        // on its own line, the source could end with a comment
+        let entry = parse(format!("{source}\n;"))?;
+        matches!(entry.statements[..], [Statement::Expression(_)]).then_some(entry)
+    })
+}
+
+/// Whether more lines are needed to complete the source: a string, parenthesis or block is left
+/// open, or parsing fails at the end of the source
+pub fn is_incomplete(source: &str) -> bool {
+    if parse_entry(source, &Arena::new()).is_some() {
+        return false;
+    }
+
+    let mut scanner = Scanner::new(source);
+    let mut tokens = Vec::new();
+    loop {
+        let (offset, _) = scanner.position();
+        match scanner.scan_lexeme() {
+            Some(Ok(Some(token))) => tokens.push(token),
+            Some(Ok(None)) => (),
+            // a string is the only lexeme failing at the end of the source
+            Some(Err(_)) if source[offset..].starts_with('"') => return true,
+            Some(Err(_)) => (),
+            None => break,
+        }
+    }
+    tokens.push(scanner.eof());
+
+    let (_, errors) = Parser::new(tokens, &Arena::new()).parse_partial();
+    errors
+        .iter()
+        .any(|e| e.token().token_type == TokenType::Eof)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{is_incomplete, parse_entry, Entry};
+    use crate::ast::{visitor::ASTPrettyPrinter, Arena};
+
+    #[test]
+    fn test_is_incomplete() {
+        for source in [
+            "{",
+            "{ print 1;\n",
+            "print (1 +",
+            "print (1 +\n2",
+            "print \"a",
+            "var a = \"a\nb",
+            "print 1",
+            "1 +",
+            "{ print ); ",
+        ] {
+            assert!(is_incomplete(source), "{source:?}");
+        }
+        for source in [
+            "",
+            "// {",
+            "print 1;",
+            "{ print 1; }",
+            "}",
+            "print );",
+            "print \"a\";",
+            "1 + 2",
+            "@",
+        ] {
+            assert!(!is_incomplete(source), "{source:?}");
+        }
+    }
+
+    #[test]
+    fn test_parse_entry() {
+        let arena = Arena::new();
+        let parse_entry = |source| parse_entry(source, &arena);
+        let print = |entry: Entry| ASTPrettyPrinter::new().print(&entry.statements);
+        assert_eq!(
+            parse_entry("a = 1 + 2").map(print),
+            parse_entry("a = 1 + 2;").map(print)
+        );
+        assert_eq!(
+            parse_entry("1 // one").map(|entry| entry.source),
+            Some("1 // one\n;".to_string())
+        );
+        assert!(parse_entry("var a = 1; { a; }").is_some());
+
+        // only a single expression can leave out its `;`
+        assert!(parse_entry("print 1").is_none());
+        assert!(parse_entry("var a = 1").is_none());
+        assert!(parse_entry("1; 2").is_none());
+        assert!(parse_entry("1 +").is_none());
+    }
+}
@@ -1,64 +1,358 @@
 use std::{
     error::Error,
-    fs::File,
-    io::{self, BufReader, Read, Write},
+    fs::{self, File},
+    io::{self, BufReader, Read},
+    time::Instant,
 };
 
 use camino::Utf8PathBuf;
+use clap::ArgEnum;
+use log::{debug, info, log_enabled, Level};
 
 use crate::{
-    // ast::visitor::ASTPrettyPrinter,
+    ast::{
+        json,
+        visitor::{ASTDotPrinter, ASTPrettyPrinter},
+        Arena,
+        NodeMap,
+        Statement,
+    },
+    cache::{Cache, CachedProgram},
     error::*,
-    interpreter::Interpreter,
+    interpreter::{
+        interrupt::{self, Interrupt},
+        resolver::{Resolution, Resolver},
+        Interpreter,
+    },
+    optimizer::{self, OptimizationLevel},
     parser::Parser,
+    repl::{self, command::Command, Input, LineReader},
     scanner::Scanner,
+    transpiler::CTranspiler,
+    vm::{compiler::Compiler, Vm},
 };
 
+/// Intermediate representations that can be emitted instead of running a program
+#[derive(Debug, Clone, Copy, ArgEnum)]
+pub enum Emit {
+    /// JSON serialization of the AST, see `ast::json`
+    AstJson,
+    /// Graphviz DOT graph of the AST
+    AstDot,
+}
+
+/// Formats of the programs that can be run
+#[derive(Debug, Clone, Copy, ArgEnum)]
+pub enum InputFormat {
+    /// Lox source code
+    Lox,
+    /// JSON serialization of an AST, as produced by `--emit=ast-json`
+    AstJson,
+}
+
+/// Languages programs can be compiled to
+#[derive(Debug, Clone, Copy, ArgEnum)]
+pub enum Target {
+    /// Self-contained C99 source file, including the runtime
+    C,
+}
+
+/// Engines that can execute a program
+#[derive(Debug, Clone, Copy, Default, ArgEnum)]
+pub enum Backend {
+    /// Tree-walking interpreter, executing the AST directly
+    #[default]
+    Tree,
+    /// Bytecode compiler and stack based virtual machine
+    Vm,
+}
+
 #[derive(Default)]
 pub struct Rox {
     had_error: bool,
     interpreter: Interpreter,
+    vm: Vm,
+    optimization_level: OptimizationLevel,
+    backend: Backend,
+    /// Where parsed programs are cached between runs of `run_file` on a file, not cached if `None`
+    cache: Option<Cache>,
 }
 
 impl Rox {
+    pub fn new(optimization_level: OptimizationLevel, backend: Backend) -> Self {
+        Self {
+            optimization_level,
+            backend,
+            ..Default::default()
+        }
+    }
+
+    /// Cache parsed programs in the given cache, see [`Rox::run_file`]
+    pub fn with_cache(mut self, cache: Cache) -> Self {
+        self.cache = Some(cache);
+        self
+    }
+
+    /// Run a Lox source file, or stdin if the path is `-`. A file is loaded from the cache when
+    /// it was already parsed by a previous run, or stored there otherwise, while stdin is never
+    /// cached, see [`Rox::run_source`].
     pub fn run_file(&mut self, file_path: Utf8PathBuf) -> FacingRoxResult<()> {
-        let f = File::open(file_path)?;
-        let mut buffer = String::new();
-        let mut reader = BufReader::new(f);
-        reader.read_to_string(&mut buffer)?;
-        self.run(&buffer)
-            .map_err(|err_vec| err_vec.into_iter().nth(1).unwrap())
+        let is_stdin = file_path == "-";
+        let buffer = Self::read_file(file_path)?;
+        if is_stdin {
+            self.run_source(&buffer)
+        } else {
+            self.run_cached(&buffer)
+        }
     }
 
-    pub fn run_prompt(&mut self) -> FacingRoxResult<()> {
-        let stdin = io::stdin(); // We get `Stdin` here.
+    /// Run a Lox program, without caching it: one-off programs would fill the cache
+    pub fn run_source(&mut self, buffer: &str) -> FacingRoxResult<()> {
+        let arena = Arena::new();
+        let ast = Self::parse_program(buffer, &arena)?;
+        self.interpret(ast, &arena);
+        Ok(())
+    }
 
-        loop {
-            print!("> ");
-            io::stdout().flush()?;
+    /// Run a Lox program, loading it from the cache when it was already parsed by a previous run,
+    /// or storing it there otherwise
+    fn run_cached(&mut self, buffer: &str) -> FacingRoxResult<()> {
+        let Some(cache) = self.cache.as_ref() else {
+            return self.run_source(buffer);
+        };
+        let arena = Arena::new();
+        let key = Cache::key(buffer, self.optimization_level);
+        let program = match cache.load(&key, &arena) {
+            Some(program) => {
+                info!("loaded program {} from the cache", key);
+                program
+            }
+            None => {
+                let ast = Self::parse_program(buffer, &arena)?;
+                let statements = optimizer::optimize(ast, self.optimization_level, &arena);
+                let resolutions = Resolver::resolve(&statements);
+                let program = CachedProgram {
+                    statements,
+                    resolutions,
+                };
+                // the program can still run without the cache
+                if let Some(Err(e)) = self.cache.as_ref().map(|c| c.store(&key, &program)) {
+                    info!("cannot cache program {}: {}", key, e);
+                }
+                program
+            }
+        };
+        self.execute(&program.statements, Some(program.resolutions));
+        Ok(())
+    }
+
+    /// Run a program from a file holding the JSON serialization of its AST, or from stdin if the
+    /// path is `-`
+    pub fn run_ast_json_file(&mut self, file_path: Utf8PathBuf) -> FacingRoxResult<()> {
+        let buffer = Self::read_file(file_path)?;
+        self.run_ast_json(&buffer)
+    }
+
+    /// Run a program from the JSON serialization of its AST
+    pub fn run_ast_json(&mut self, buffer: &str) -> FacingRoxResult<()> {
+        let arena = Arena::new();
+        let ast = json::from_json(buffer, &arena)?;
+        self.interpret(ast, &arena);
+        Ok(())
+    }
 
-            let mut buffer = String::new();
-            let read = stdin.read_line(&mut buffer)?;
+    /// Compile a file to bytecode, then print the disassembled chunk instead of running it
+    pub fn disassemble_file(&mut self, file_path: Utf8PathBuf) -> FacingRoxResult<()> {
+        let buffer = Self::read_file(file_path.clone())?;
+        let arena = Arena::new();
+        let ast = Self::parse_program(&buffer, &arena)?;
+        let ast = optimizer::optimize(ast, self.optimization_level, &arena);
 
-            if read == 0 {
-                // user entered C^D
-                break;
+        let chunk = Compiler::compile(&ast)?;
+        print!("{}", chunk.disassemble(file_path.as_str()));
+        Ok(())
+    }
+
+    /// Scan and parse a file, then print the requested representation of it instead of running it
+    pub fn emit_file(&mut self, file_path: Utf8PathBuf, emit: Emit) -> FacingRoxResult<()> {
+        let buffer = Self::read_file(file_path)?;
+        let arena = Arena::new();
+        let ast = Self::parse_program(&buffer, &arena)?;
+
+        match emit {
+            Emit::AstJson => println!("{}", json::to_json(&ast)?),
+            Emit::AstDot => print!("{}", ASTDotPrinter::new().print(&ast)),
+        }
+        Ok(())
+    }
+
+    /// Compile a file to the given target language, write the result to `output` or print it
+    pub fn compile_file(
+        &mut self,
+        file_path: Utf8PathBuf,
+        target: Target,
+        output: Option<Utf8PathBuf>,
+    ) -> FacingRoxResult<()> {
+        let buffer = Self::read_file(file_path)?;
+        let arena = Arena::new();
+        let ast = Self::parse_program(&buffer, &arena)?;
+        let ast = optimizer::optimize(ast, self.optimization_level, &arena);
+
+        let compiled = match target {
+            Target::C => CTranspiler::transpile(&ast),
+        };
+        match output {
+            Some(output) => fs::write(output, compiled)?,
+            None => print!("{compiled}"),
+        }
+        Ok(())
+    }
+
+    /// Run what is typed at the prompt of `reader`, until `exit` or the end of the input.
+    ///
+    /// Lines are gathered until they make a complete program, see [`repl::is_incomplete`], with a
+    /// continuation prompt. Ctrl-C discards them, stops the running code, see
+    /// [`interrupt::Catcher`], and exits when pressed twice at an empty prompt. A line starting
+    /// with `:` at an empty prompt is a [`Command`].
+    ///
+    /// The values of the expression statements are echoed.
+    pub fn run_prompt(&mut self, reader: &mut LineReader) -> FacingRoxResult<()> {
+        let mut buffer = String::new();
+        // sources which ran successfully, for `:save`
+        let mut inputs = Vec::new();
+        let mut ended = false;
+        let mut interrupted = false;
+        while !ended {
+            let names = match self.backend {
+                Backend::Tree => self
+                    .interpreter
+                    .environment()
+                    .global_names()
+                    .cloned()
+                    .collect(),
+                Backend::Vm => self.vm.global_names().cloned().collect(),
+            };
+            reader.set_names(names);
+            let prompt = if buffer.is_empty() { "> " } else { "... " };
+            match reader.read_line(prompt)? {
+                Input::Line(line) if buffer.is_empty() && line.trim_start().starts_with(':') => {
+                    reader.add_history(line.trim_end());
+                    let _catcher = interrupt::Catcher::new(self.interrupt());
+                    let result = line
+                        .parse()
+                        .map_err(FacingRoxError::from)
+                        .and_then(|command| self.run_command(command, &mut inputs));
+                    if let Err(e) = result {
+                        eprintln!("{}", e);
+                    }
+                    self.reset_error();
+                    continue;
+                }
+                Input::Line(line) => {
+                    interrupted = false;
+                    if buffer.is_empty()
+                        && matches!(line.trim_end(), "exit" | "exit()" | "quit" | "quit()")
+                    {
+                        break;
+                    }
+                    buffer.push_str(&line);
+                    buffer.push('\n');
+                    if repl::is_incomplete(&buffer) {
+                        continue;
+                    }
+                }
+                // a second Ctrl-C in a row at an empty prompt exits
+                Input::Interrupted if buffer.is_empty() && interrupted => break,
+                Input::Interrupted => {
+                    if buffer.is_empty() {
+                        eprintln!("(press Ctrl-C again to exit)");
+                        interrupted = true;
+                    }
+                    buffer.clear();
+                    continue;
+                }
+                // what was left incomplete still runs, to report its errors
+                Input::Eof if !buffer.is_empty() => ended = true,
+                Input::Eof => break,
             }
 
-            match buffer.trim_end() {
-                "exit" | "exit()" | "quit" | "quit()" => break,
-                a => {
-                    let r = self.run(a);
+            let source = buffer.trim_end();
+            reader.add_history(source);
+            let _catcher = interrupt::Catcher::new(self.interrupt());
+            self.run_input(source, &mut inputs);
+            buffer.clear();
+        }
+        Ok(())
+    }
+
+    /// Request to stop the code running on the backend
+    fn interrupt(&self) -> &Interrupt {
+        match self.backend {
+            Backend::Tree => self.interpreter.interrupt(),
+            Backend::Vm => self.vm.interrupt(),
+        }
+    }
 
-                    if r.is_err() {
-                        self.reset_error();
+    /// Run a command of the REPL, `inputs` being the sources which ran successfully so far
+    fn run_command(&mut self, command: Command, inputs: &mut Vec<String>) -> FacingRoxResult<()> {
+        match command {
+            Command::Help => println!("{}", Command::HELP),
+            Command::Env => match self.backend {
+                Backend::Tree => print!("{}", self.interpreter.environment()),
+                Backend::Vm => print!("{}", self.vm),
+            },
+            Command::Tokens(code) => {
+                if let Ok(tokens) = self.handle_errors(Scanner::new(&code).scan_tokens()) {
+                    for token in tokens {
+                        println!("{:?} {:?}", token.token_type, token.lexeme);
                     }
                 }
             }
+            Command::Ast(code) => {
+                let arena = Arena::new();
+                let ast = match repl::parse_entry(&code, &arena) {
+                    Some(entry) => Ok(entry.statements),
+                    None => self.parse(&code, &arena),
+                };
+                if let Ok(ast) = ast {
+                    println!("{}", ASTPrettyPrinter::new().print(&ast));
+                }
+            }
+            Command::Load(path) => {
+                let source = Self::read_file(path)?;
+                if self.run(&source).is_ok() && !self.had_error {
+                    inputs.push(source.trim_end().to_string());
+                }
+            }
+            Command::Reset => {
+                self.interpreter = Interpreter::default();
+                self.vm = Vm::default();
+                inputs.clear();
+            }
+            Command::Time(code) => {
+                let start = Instant::now();
+                self.run_input(&code, inputs);
+                println!("took {:?}", start.elapsed());
+            }
+            Command::Save(path) => {
+                let mut source = inputs.join("\n");
+                source.push('\n');
+                fs::write(path, source)?;
+            }
         }
         Ok(())
     }
 
+    /// Run an entry of the REPL, adding its source to `inputs` if it ran successfully
+    fn run_input(&mut self, source: &str, inputs: &mut Vec<String>) {
+        if let Ok(source) = self.run_entry(source) {
+            if !self.had_error {
+                inputs.push(source);
+            }
+        }
+        self.reset_error();
+    }
+
     fn handle_errors<T, E>(&mut self, result: Result<T, Vec<E>>) -> FacingRoxResults<T>
     where
         E: Into<FacingRoxError> + Error,
@@ -78,19 +372,112 @@ impl Rox {
         self.had_error = false;
     }
 
-    fn run(&mut self, buffer: &str) -> FacingRoxResults<()> {
+    /// Read a file, or stdin if the path is `-`
+    fn read_file(file_path: Utf8PathBuf) -> FacingRoxResult<String> {
+        let mut buffer = String::new();
+        if file_path == "-" {
+            io::stdin().read_to_string(&mut buffer)?;
+            return Ok(buffer);
+        }
+        let f = File::open(file_path)?;
+        let mut reader = BufReader::new(f);
+        reader.read_to_string(&mut buffer)?;
+        Ok(buffer)
+    }
+
+    fn parse<'a>(
+        &mut self,
+        buffer: &str,
+        arena: &'a Arena<'a>,
+    ) -> FacingRoxResults<Vec<Statement<'a>>> {
         let scanner = Scanner::new(buffer);
         let tokens = self.handle_errors(scanner.scan_tokens())?;
 
-        let p = Parser::new(tokens);
-        let ast = self.handle_errors(p.parse())?;
+        let p = Parser::new(tokens, arena);
+        self.handle_errors(p.parse())
+    }
+
+    /// Scan and parse a whole program, returning its errors rather than printing them
+    fn parse_program<'a>(
+        buffer: &str,
+        arena: &'a Arena<'a>,
+    ) -> FacingRoxResult<Vec<Statement<'a>>> {
+        let into_error = |errors: Vec<FacingRoxError>| match <[_; 1]>::try_from(errors) {
+            Ok([error]) => error,
+            Err(errors) => FacingRoxError::Errors(errors),
+        };
+        let tokens = Scanner::new(buffer)
+            .scan_tokens()
+            .map_err(|errors| into_error(errors.into_iter().map(Into::into).collect()))?;
+        Parser::new(tokens, arena)
+            .parse()
+            .map_err(|errors| into_error(errors.into_iter().map(Into::into).collect()))
+    }
+
+    fn interpret<'a>(&mut self, ast: Vec<Statement<'a>>, arena: &'a Arena<'a>) {
+        let ast = optimizer::optimize(ast, self.optimization_level, arena);
+        self.execute(&ast, None);
+    }
 
-        // println!("ast: {} \n", ASTPrettyPrinter::new().print(&ast));
+    /// Execute an optimized program, with its variables already resolved if `resolutions` is set
+    fn execute(&mut self, ast: &[Statement], resolutions: Option<NodeMap<Resolution>>) {
+        if log_enabled!(Level::Debug) {
+            debug!("ast:\n{}", ASTPrettyPrinter::new().print(ast));
+        }
 
-        if let Err(e) = self.interpreter.interpret(&ast) {
+        let result = match (self.backend, resolutions) {
+            (Backend::Tree, Some(resolutions)) => {
+                self.interpreter.interpret_resolved(ast, resolutions)
+            }
+            (Backend::Tree, None) => self.interpreter.interpret(ast),
+            (Backend::Vm, _) => match Compiler::compile(ast) {
+                Ok(chunk) => self.vm.run(&chunk),
+                Err(e) => {
+                    self.had_error = true;
+                    eprintln!("{}", e);
+                    return;
+                }
+            },
+        };
+        if let Err(e) = result {
             self.had_error = true;
             eprintln!("{}", e);
         }
+    }
+
+    /// Run an entry of the REPL, see [`repl::parse_entry`], [`Interpreter::interpret_echoing`] and
+    /// [`Compiler::compile_echoing`]. Returns the source that ran.
+    fn run_entry(&mut self, buffer: &str) -> FacingRoxResults<String> {
+        let arena = Arena::new();
+        let (source, ast) = match repl::parse_entry(buffer, &arena) {
+            Some(entry) => (entry.source, entry.statements),
+            None => (buffer.to_string(), self.parse(buffer, &arena)?),
+        };
+        let ast = optimizer::optimize(ast, self.optimization_level, &arena);
+        if log_enabled!(Level::Debug) {
+            debug!("ast:\n{}", ASTPrettyPrinter::new().print(&ast));
+        }
+
+        let result = match self.backend {
+            Backend::Tree => self
+                .interpreter
+                .interpret_echoing(&ast)
+                .map_err(FacingRoxError::from),
+            Backend::Vm => Compiler::compile_echoing(&ast)
+                .map_err(FacingRoxError::from)
+                .and_then(|chunk| self.vm.run(&chunk).map_err(FacingRoxError::from)),
+        };
+        if let Err(e) = result {
+            self.had_error = true;
+            eprintln!("{}", e);
+        }
+        Ok(source)
+    }
+
+    fn run(&mut self, buffer: &str) -> FacingRoxResults<()> {
+        let arena = Arena::new();
+        let ast = self.parse(buffer, &arena)?;
+        self.interpret(ast, &arena);
 
         Ok(())
     }
@@ -2,7 +2,7 @@ use std::fmt::Display;
 
 use thiserror::Error;
 
-#[derive(Error, Debug, PartialEq)]
+#[derive(Error, Debug, PartialEq, Clone)]
 pub struct ScannerError {
     line_index: usize,
     msg: String,
@@ -12,6 +12,15 @@ impl ScannerError {
     pub fn new(line_index: usize, msg: String) -> Self {
         Self { line_index, msg }
     }
+
+    /// Line the error is reported on
+    pub fn line_index(&self) -> usize {
+        self.line_index
+    }
+
+    pub fn msg(&self) -> &str {
+        &self.msg
+    }
 }
 
 impl Display for ScannerError {
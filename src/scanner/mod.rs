@@ -2,10 +2,13 @@ pub mod error;
 use phf::phf_map;
 
 use self::error::{ScannerError, ScannerResult, ScannerResults};
-use crate::token::{Token, TokenType};
+use crate::{
+    interner,
+    token::{Span, Token, TokenType},
+};
 
 /// Perfect HashMap mapping string keywords to their token type
-static KEYWORDS: phf::Map<&'static str, TokenType> = phf_map! {
+pub(crate) static KEYWORDS: phf::Map<&'static str, TokenType> = phf_map! {
     "and" => TokenType::And,
     "class" => TokenType::Class,
     "else" => TokenType::Else,
@@ -32,12 +35,14 @@ pub struct Scanner<'a> {
     /// internal state: holds the built tokens
     tokens: Vec<Token>,
 
-    /// internal state: start index in the source of the token being scanned
+    /// internal state: start byte offset in the source of the token being scanned
     start_index: usize,
-    /// internal state: index in the source of the lexeme being scanned
+    /// internal state: byte offset in the source of the lexeme being scanned
     current_index: usize,
     /// internal state: index of the line being scanned
     line_index: usize,
+    /// internal state: index of the line the token being scanned starts on
+    start_line_index: usize,
 }
 
 impl<'a> Scanner<'a> {
@@ -49,6 +54,17 @@ impl<'a> Scanner<'a> {
             start_index: 0,
             current_index: 0,
             line_index: 0,
+            start_line_index: 0,
+        }
+    }
+
+    /// Create a Scanner resuming at the byte offset `index` of the source buffer, which is on the
+    /// line `line_index`. The offset has to be the start of a lexeme, e.g. the end of a token.
+    pub fn resume(source_buffer: &'a str, index: usize, line_index: usize) -> Self {
+        Self {
+            current_index: index,
+            line_index,
+            ..Self::new(source_buffer)
         }
     }
 
@@ -58,11 +74,8 @@ impl<'a> Scanner<'a> {
     /// If any errors are encountered during the scanning process, returns them here.
     pub fn scan_tokens(mut self) -> ScannerResults<Vec<Token>> {
         let mut errors_encountered: Vec<ScannerError> = Vec::new();
-
-        while self.current_index < self.source_buffer.len() {
-            // starting scanning for a new token, reset the start index
-            self.start_index = self.current_index;
-            match self.scan_token() {
+        while let Some(result) = self.scan_lexeme() {
+            match result {
                 Ok(r) => {
                     // if we have a token to add, add it
                     // this can be None for some reasons, for instance finding whitespaces
@@ -73,15 +86,47 @@ impl<'a> Scanner<'a> {
                 Err(e) => errors_encountered.push(e),
             }
         }
+
         if errors_encountered.is_empty() {
-            self.tokens
-                .push(Token::new(TokenType::Eof, String::new(), self.line_index));
+            self.tokens.push(self.eof());
             Ok(self.tokens)
         } else {
             Err(errors_encountered)
         }
     }
 
+    /// Scan the lexeme starting at [`Scanner::position`], `None` once the whole source is
+    /// scanned. Lexemes which aren't tokens, such as whitespaces and comments, give `Ok(None)`.
+    pub fn scan_lexeme(&mut self) -> Option<ScannerResult<Option<Token>>> {
+        if self.current_index >= self.source_buffer.len() {
+            return None;
+        }
+        // starting scanning for a new token, reset the start index
+        self.start_index = self.current_index;
+        self.start_line_index = self.line_index;
+        Some(self.scan_token())
+    }
+
+    /// Byte offset and line of the next lexeme to scan
+    pub fn position(&self) -> (usize, usize) {
+        (self.current_index, self.line_index)
+    }
+
+    /// Token ending the source, at the current position
+    pub fn eof(&self) -> Token {
+        Token::new(
+            TokenType::Eof,
+            interner::intern(""),
+            self.line_index,
+            Span::new(
+                self.current_index,
+                self.current_index,
+                self.line_index,
+                self.line_index,
+            ),
+        )
+    }
+
     /// Method responsible for the actual scanning of a token
     fn scan_token(&mut self) -> ScannerResult<Option<Token>> {
         match self.advance() {
@@ -129,17 +174,16 @@ impl<'a> Scanner<'a> {
             })),
             '/' => Ok(if self.advance_if_equal('/') {
                 // A comment goes until the end of the line.
-
-                while let Some(next_c) = self.peek() {
-                    if next_c == '\n' {
-                        break;
-                    }
-                    self.advance();
-                }
+                self.skip_line();
                 None
             } else {
                 Some(self.build_simple_token(TokenType::Slash))
             }),
+            // the interpreter line of an executable script, e.g. `#!/usr/bin/env rox`
+            '#' if self.start_index == 0 && self.advance_if_equal('!') => {
+                self.skip_line();
+                Ok(None)
+            }
             '"' => self.scan_string(),
             ' ' => Ok(None),
             '\r' => Ok(None),
@@ -161,16 +205,30 @@ impl<'a> Scanner<'a> {
     /// Build a simple token representing the source_buffer lexemes in the interval
     /// `[self.start_index..self.current_index]`
     fn build_simple_token(&self, token_type: TokenType) -> Token {
+        self.build_complex_token(
+            token_type,
+            &self.source_buffer[self.start_index..self.current_index],
+        )
+    }
+
+    /// Build a complex token out of a specified lexeme string
+    fn build_complex_token(&self, token_type: TokenType, lexeme: &str) -> Token {
         Token::new(
             token_type,
-            self.source_buffer[self.start_index..self.current_index].to_owned(),
+            interner::intern(lexeme),
             self.line_index,
+            self.current_span(),
         )
     }
 
-    /// Build a complex token out of a specified lexeme string
-    fn build_complex_token(&self, token_type: TokenType, lexeme: String) -> Token {
-        Token::new(token_type, lexeme, self.line_index)
+    /// Span of the token being scanned, from `self.start_index` to `self.current_index`
+    fn current_span(&self) -> Span {
+        Span::new(
+            self.start_index,
+            self.current_index,
+            self.start_line_index,
+            self.line_index,
+        )
     }
 
     /// Scan the internal buffer from the current token until a string ending delimiter lexeme is
@@ -204,7 +262,7 @@ impl<'a> Scanner<'a> {
             TokenType::String,
             // don't forget to account for the " delimiters on both sides when extracting the
             // lexeme string
-            self.source_buffer[self.start_index + 1..self.current_index - 1].to_owned(),
+            &self.source_buffer[self.start_index + 1..self.current_index - 1],
         )))
     }
 
@@ -225,7 +283,7 @@ impl<'a> Scanner<'a> {
 
         Ok(Some(self.build_complex_token(
             TokenType::Number,
-            self.source_buffer[self.start_index..self.current_index].to_owned(),
+            &self.source_buffer[self.start_index..self.current_index],
         )))
     }
 
@@ -237,43 +295,46 @@ impl<'a> Scanner<'a> {
         let text = &self.source_buffer[self.start_index..self.current_index];
 
         Ok(Some(if let Some(token_type) = KEYWORDS.get(text) {
-            self.build_complex_token(*token_type, text.to_owned())
+            self.build_complex_token(*token_type, text)
         } else {
-            self.build_complex_token(TokenType::Identifier, text.to_owned())
+            self.build_complex_token(TokenType::Identifier, text)
         }))
     }
 
     /// return the current char in source and advance cursor by one
     fn advance(&mut self) -> char {
-        self.current_index += 1;
-        self.source_buffer
-            .chars()
-            .nth(self.current_index - 1)
-            .unwrap()
+        let c = self.peek().unwrap();
+        self.current_index += c.len_utf8();
+        c
     }
 
     /// only consume the next char if it matches the expected one
     fn advance_if_equal(&mut self, expected: char) -> bool {
-        match self.source_buffer.chars().nth(self.current_index) {
-            Some(c) => {
-                if c != expected {
-                    return false;
-                }
-            }
-            None => return false,
+        if self.peek() != Some(expected) {
+            return false;
         }
-        self.current_index += 1;
+        self.current_index += expected.len_utf8();
         true
     }
 
+    /// consume the rest of the line, up to the line ending
+    fn skip_line(&mut self) {
+        while let Some(next_c) = self.peek() {
+            if next_c == '\n' {
+                break;
+            }
+            self.advance();
+        }
+    }
+
     /// peek the current character in the source
     fn peek(&self) -> Option<char> {
-        self.source_buffer.chars().nth(self.current_index)
+        self.source_buffer[self.current_index..].chars().next()
     }
 
     /// peek the next character in source
     fn peek_next(&self) -> Option<char> {
-        self.source_buffer.chars().nth(self.current_index + 1)
+        self.source_buffer[self.current_index..].chars().nth(1)
     }
 
     /// helper to check if a character is a digit
@@ -300,7 +361,7 @@ mod test {
     use super::Scanner;
     use crate::{
         scanner::error::ScannerError,
-        token::{Token, TokenType},
+        token::{Span, Token, TokenType},
     };
 
     #[test]
@@ -310,9 +371,9 @@ mod test {
         assert_eq!(
             a,
             vec![
-                Token::new(TokenType::LeftParen, "(".into(), 0),
-                Token::new(TokenType::RightParen, ")".into(), 0),
-                Token::new(TokenType::Eof, "".into(), 0)
+                Token::new(TokenType::LeftParen, "(".into(), 0, Span::new(0, 1, 0, 0)),
+                Token::new(TokenType::RightParen, ")".into(), 0, Span::new(1, 2, 0, 0)),
+                Token::new(TokenType::Eof, "".into(), 0, Span::new(2, 2, 0, 0))
             ]
         );
     }
@@ -332,34 +393,97 @@ mod test {
         assert_eq!(
             a,
             vec![
-                Token::new(TokenType::LeftParen, "(".into(), 1),
-                Token::new(TokenType::LeftParen, "(".into(), 1),
-                Token::new(TokenType::RightParen, ")".into(), 1),
-                Token::new(TokenType::RightParen, ")".into(), 1),
-                Token::new(TokenType::LeftBrace, "{".into(), 1),
-                Token::new(TokenType::RightBrace, "}".into(), 1),
-                Token::new(TokenType::Bang, "!".into(), 2),
-                Token::new(TokenType::Star, "*".into(), 2),
-                Token::new(TokenType::Plus, "+".into(), 2),
-                Token::new(TokenType::Minus, "-".into(), 2),
-                Token::new(TokenType::Slash, "/".into(), 2),
-                Token::new(TokenType::Equal, "=".into(), 2),
-                Token::new(TokenType::Less, "<".into(), 2),
-                Token::new(TokenType::Greater, ">".into(), 2),
-                Token::new(TokenType::LessEqual, "<=".into(), 2),
-                Token::new(TokenType::EqualEqual, "==".into(), 2),
-                Token::new(TokenType::Number, "1234.567098".into(), 3),
-                Token::new(TokenType::Plus, "+".into(), 3),
-                Token::new(TokenType::Number, "23".into(), 3),
-                Token::new(TokenType::Number, "42".into(), 4),
-                Token::new(TokenType::String, "aaaaaa".into(), 5),
-                Token::new(TokenType::Or, "or".into(), 6),
-                Token::new(TokenType::Identifier, "baba_is_you".into(), 7),
-                Token::new(TokenType::Slash, "/".into(), 8),
-                Token::new(TokenType::Eof, "".into(), 8),
+                Token::new(TokenType::LeftParen, "(".into(), 1, Span::new(29, 30, 1, 1)),
+                Token::new(TokenType::LeftParen, "(".into(), 1, Span::new(30, 31, 1, 1)),
+                Token::new(
+                    TokenType::RightParen,
+                    ")".into(),
+                    1,
+                    Span::new(32, 33, 1, 1)
+                ),
+                Token::new(
+                    TokenType::RightParen,
+                    ")".into(),
+                    1,
+                    Span::new(33, 34, 1, 1)
+                ),
+                Token::new(TokenType::LeftBrace, "{".into(), 1, Span::new(34, 35, 1, 1)),
+                Token::new(
+                    TokenType::RightBrace,
+                    "}".into(),
+                    1,
+                    Span::new(35, 36, 1, 1)
+                ),
+                Token::new(TokenType::Bang, "!".into(), 2, Span::new(63, 64, 2, 2)),
+                Token::new(TokenType::Star, "*".into(), 2, Span::new(64, 65, 2, 2)),
+                Token::new(TokenType::Plus, "+".into(), 2, Span::new(65, 66, 2, 2)),
+                Token::new(TokenType::Minus, "-".into(), 2, Span::new(66, 67, 2, 2)),
+                Token::new(TokenType::Slash, "/".into(), 2, Span::new(67, 68, 2, 2)),
+                Token::new(TokenType::Equal, "=".into(), 2, Span::new(68, 69, 2, 2)),
+                Token::new(TokenType::Less, "<".into(), 2, Span::new(69, 70, 2, 2)),
+                Token::new(TokenType::Greater, ">".into(), 2, Span::new(70, 71, 2, 2)),
+                Token::new(
+                    TokenType::LessEqual,
+                    "<=".into(),
+                    2,
+                    Span::new(72, 74, 2, 2)
+                ),
+                Token::new(
+                    TokenType::EqualEqual,
+                    "==".into(),
+                    2,
+                    Span::new(75, 77, 2, 2)
+                ),
+                Token::new(
+                    TokenType::Number,
+                    "1234.567098".into(),
+                    3,
+                    Span::new(101, 112, 3, 3)
+                ),
+                Token::new(TokenType::Plus, "+".into(), 3, Span::new(113, 114, 3, 3)),
+                Token::new(TokenType::Number, "23".into(), 3, Span::new(114, 116, 3, 3)),
+                Token::new(TokenType::Number, "42".into(), 4, Span::new(125, 127, 4, 4)),
+                Token::new(
+                    TokenType::String,
+                    "aaaaaa".into(),
+                    5,
+                    Span::new(136, 144, 5, 5)
+                ),
+                Token::new(TokenType::Or, "or".into(), 6, Span::new(153, 155, 6, 6)),
+                Token::new(
+                    TokenType::Identifier,
+                    "baba_is_you".into(),
+                    7,
+                    Span::new(164, 175, 7, 7)
+                ),
+                Token::new(TokenType::Slash, "/".into(), 8, Span::new(184, 185, 8, 8)),
+                Token::new(TokenType::Eof, "".into(), 8, Span::new(185, 185, 8, 8)),
             ]
         );
     }
+    #[test]
+    fn test_multiline_string_span() {
+        let s = Scanner::new("a \"b\nc\" d");
+        let a = s.scan_tokens().unwrap();
+        assert_eq!(
+            a[1],
+            Token::new(TokenType::String, "b\nc".into(), 1, Span::new(2, 7, 0, 1))
+        );
+        assert_eq!(a[2].span, Span::new(8, 9, 1, 1));
+    }
+
+    #[test]
+    fn test_non_ascii_string() {
+        let s = Scanner::new("\"héllo\" + x");
+        let a = s.scan_tokens().unwrap();
+        assert_eq!(
+            a[0],
+            Token::new(TokenType::String, "héllo".into(), 0, Span::new(0, 8, 0, 0))
+        );
+        assert_eq!(a[2].lexeme, "x".into());
+        assert_eq!(a[2].span, Span::new(11, 12, 0, 0));
+    }
+
     #[test]
     fn test_errors_on_unknown() {
         let s = Scanner::new("@#(");
@@ -370,4 +494,21 @@ mod test {
             assert_eq!(e, err);
         }
     }
+
+    #[test]
+    fn test_shebang() {
+        let s = Scanner::new("#!/usr/bin/env rox\nprint 1;");
+        let a = s.scan_tokens().unwrap();
+        assert_eq!(
+            a[0],
+            Token::new(TokenType::Print, "print".into(), 1, Span::new(19, 24, 1, 1))
+        );
+
+        // only the first line can be one
+        let s = Scanner::new(" #!/usr/bin/env rox");
+        assert_eq!(
+            s.scan_tokens().unwrap_err(),
+            vec![ScannerError::new(0, "Unexpected character".into())]
+        );
+    }
 }
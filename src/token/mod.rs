@@ -1,20 +1,31 @@
+mod span;
 mod token_type;
 
+use std::rc::Rc;
+
+use serde::{Deserialize, Serialize};
+pub use span::Span;
 pub use token_type::TokenType;
 
-#[derive(Debug, PartialEq, Clone)]
+use crate::interner;
+
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
 pub struct Token {
     pub token_type: TokenType,
-    pub lexeme: String,
+    /// Interned, so that tokens and the AST nodes holding them are cheap to copy
+    #[serde(deserialize_with = "interner::deserialize")]
+    pub lexeme: Rc<str>,
     pub line: usize,
+    pub span: Span,
 }
 
 impl Token {
-    pub fn new(token_type: TokenType, lexeme: String, line: usize) -> Token {
+    pub fn new(token_type: TokenType, lexeme: Rc<str>, line: usize, span: Span) -> Token {
         Token {
             token_type,
             lexeme,
             line,
+            span,
         }
     }
 }
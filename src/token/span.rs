@@ -0,0 +1,34 @@
+use serde::{Deserialize, Serialize};
+
+/// Region of the source covered by a token or an AST node.
+///
+/// `start..end` are the byte offsets of the first character and of the one following the last
+/// character in the source, and `start_line`/`end_line` the lines these characters are on.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default, Serialize, Deserialize)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+    pub start_line: usize,
+    pub end_line: usize,
+}
+
+impl Span {
+    pub fn new(start: usize, end: usize, start_line: usize, end_line: usize) -> Self {
+        Self {
+            start,
+            end,
+            start_line,
+            end_line,
+        }
+    }
+
+    /// Build the span going from the start of `self` to the end of `other`
+    pub fn to(self, other: Span) -> Span {
+        Span {
+            start: self.start,
+            end: other.end,
+            start_line: self.start_line,
+            end_line: other.end_line,
+        }
+    }
+}
@@ -0,0 +1,322 @@
+use std::{collections::HashMap, fmt::Write, rc::Rc};
+
+use crate::{
+    ast::{
+        expression::{Assign, Binary, ErrorExpr, Grouping, Literal, LiteralValue, Unary, Variable},
+        statement::{
+            BlockStatement,
+            ErrorStatement,
+            ExpressionStatement,
+            PrintStatement,
+            VariableStatement,
+        },
+        visitor::{ExprVisitor, StatementVisitor},
+        NodeMap,
+        Statement,
+    },
+    interpreter::resolver::{Resolution, Resolver},
+    token::TokenType,
+};
+
+/// Runtime prepended to every transpiled program
+const RUNTIME: &str = include_str!("runtime.c");
+
+/// Transpiles an AST into a self-contained C99 program reproducing the semantics of the
+/// tree-walking interpreter.
+///
+/// Every expression is evaluated into its own temporary, one C statement after the other, so that
+/// operands are evaluated from left to right as in the interpreter. Locals become C variables
+/// named after their block and slot, globals are entries of a table which knows whether they're
+/// defined yet.
+pub struct CTranspiler {
+    body: String,
+    indent_lvl: usize,
+    resolutions: NodeMap<Resolution>,
+    globals: Vec<Rc<str>>,
+    global_indices: HashMap<Rc<str>, usize>,
+    /// Open blocks, as their index and the number of slots declared in them so far
+    blocks: Vec<(usize, usize)>,
+    next_block: usize,
+    next_temporary: usize,
+}
+
+impl CTranspiler {
+    /// Transpile a whole program into the source of a C file
+    pub fn transpile(statements: &[Statement]) -> String {
+        let mut transpiler = CTranspiler {
+            body: String::new(),
+            indent_lvl: 1,
+            resolutions: Resolver::resolve(statements),
+            globals: Vec::new(),
+            global_indices: HashMap::new(),
+            blocks: Vec::new(),
+            next_block: 0,
+            next_temporary: 0,
+        };
+        for statement in statements {
+            statement.accept(&mut transpiler);
+        }
+
+        let mut output = String::from(RUNTIME);
+        if !transpiler.globals.is_empty() {
+            output.push_str("\nstatic LoxGlobal globals[] = {\n");
+            for name in transpiler.globals.iter() {
+                writeln!(output, "    LOX_GLOBAL({}),", c_string(name)).unwrap();
+            }
+            output.push_str("};\n");
+        }
+        output.push_str("\nint main(void) {\n");
+        output.push_str(&transpiler.body);
+        output.push_str("    return 0;\n}\n");
+        output
+    }
+
+    fn line(&mut self, line: &str) {
+        self.body.push_str(&"    ".repeat(self.indent_lvl));
+        self.body.push_str(line);
+        self.body.push('\n');
+    }
+
+    /// Emit the declaration of a new temporary holding the given C expression, returns its name
+    fn temporary(&mut self, value: &str) -> String {
+        let name = format!("t{}", self.next_temporary);
+        self.next_temporary += 1;
+        self.line(&format!("Value {name} = {value};"));
+        name
+    }
+
+    /// Name of the C variable holding the `slot`th local of the block `depth` blocks above the
+    /// innermost
+    fn local(&self, depth: usize, slot: usize) -> String {
+        let (block, _) = self.blocks[self.blocks.len() - 1 - depth];
+        format!("b{block}_{slot}")
+    }
+
+    /// Reference to the entry of the globals table of the given variable
+    fn global(&mut self, name: &Rc<str>) -> String {
+        let index = match self.global_indices.get(name) {
+            Some(index) => *index,
+            None => {
+                self.globals.push(name.clone());
+                self.global_indices
+                    .insert(name.clone(), self.globals.len() - 1);
+                self.globals.len() - 1
+            }
+        };
+        format!("&globals[{index}]")
+    }
+}
+
+impl ExprVisitor for CTranspiler {
+    type Return = String;
+
+    fn visit_unary(&mut self, unary: &Unary) -> Self::Return {
+        let right = unary.expr.accept(self);
+        let function = match unary.op.token_type {
+            TokenType::Minus => "lox_negate",
+            TokenType::Bang => "lox_not",
+            t => unreachable!("parser doesn't build unary expressions of {t:?}"),
+        };
+        self.temporary(&format!("{function}({right})"))
+    }
+
+    fn visit_binary(&mut self, binary: &Binary) -> Self::Return {
+        let left = binary.left.accept(self);
+        let right = binary.right.accept(self);
+        let function = match binary.op.token_type {
+            TokenType::Plus => "lox_add",
+            TokenType::Minus => "lox_subtract",
+            TokenType::Star => "lox_multiply",
+            TokenType::Slash => "lox_divide",
+            TokenType::EqualEqual => "lox_equal",
+            TokenType::BangEqual => "lox_not_equal",
+            TokenType::Greater => "lox_greater",
+            TokenType::GreaterEqual => "lox_greater_equal",
+            TokenType::Less => "lox_less",
+            TokenType::LessEqual => "lox_less_equal",
+            t => unreachable!("parser doesn't build binary expressions of {t:?}"),
+        };
+        self.temporary(&format!("{function}({left}, {right})"))
+    }
+
+    fn visit_grouping(&mut self, grouping: &Grouping) -> Self::Return {
+        grouping.expr.accept(self)
+    }
+
+    fn visit_literal(&mut self, literal: &Literal) -> Self::Return {
+        let value = match &literal.value {
+            LiteralValue::Nil => "LOX_NIL_VALUE".to_owned(),
+            LiteralValue::Boolean(v) => format!("lox_boolean({v})"),
+            LiteralValue::Number(v) if v.is_finite() => format!("lox_number({v:?})"),
+            // folded constants can be infinite or NaN, which have no C literal
+            LiteralValue::Number(v) => format!("lox_number_bits({:#x}ULL)", v.to_bits()),
+            LiteralValue::String(v) => format!("lox_string({}, {})", c_string(v), v.len()),
+        };
+        self.temporary(&value)
+    }
+
+    fn visit_variable(&mut self, variable: &Variable) -> Self::Return {
+        // always copied, the variable could be assigned before the value is used
+        let value = match self.resolutions[&variable.id] {
+            Resolution::Local { depth, slot } => self.local(depth, slot),
+            _ => format!("lox_get_global({})", self.global(&variable.name.lexeme)),
+        };
+        self.temporary(&value)
+    }
+
+    fn visit_assign(&mut self, assign: &Assign) -> Self::Return {
+        let value = assign.value.accept(self);
+        match self.resolutions[&assign.id] {
+            Resolution::Local { depth, slot } => {
+                let local = self.local(depth, slot);
+                self.line(&format!("{local} = {value};"));
+            }
+            Resolution::Global => {
+                let global = self.global(&assign.name.lexeme);
+                self.line(&format!("lox_set_global({global}, {value});"));
+            }
+            Resolution::Undefined => self.line(&format!(
+                "lox_undefined_assignment({});",
+                c_string(&assign.name.lexeme)
+            )),
+        }
+        value
+    }
+
+    fn visit_error(&mut self, _error: &ErrorExpr) -> Self::Return {
+        unreachable!("programs which failed to parse are never transpiled")
+    }
+}
+
+impl StatementVisitor for CTranspiler {
+    type Return = ();
+
+    fn visit_print(&mut self, statement: &PrintStatement) -> Self::Return {
+        let value = statement.expr.accept(self);
+        self.line(&format!("lox_print({value});"));
+    }
+
+    fn visit_expression(&mut self, statement: &ExpressionStatement) -> Self::Return {
+        let value = statement.expr.accept(self);
+        self.line(&format!("(void){value};"));
+    }
+
+    fn visit_variable(&mut self, variable: &VariableStatement) -> Self::Return {
+        let value = match variable.initializer.as_ref() {
+            Some(initializer) => initializer.accept(self),
+            None => self.temporary("LOX_NIL_VALUE"),
+        };
+        match self.resolutions[&variable.id] {
+            Resolution::Local { slot, .. } => {
+                let local = self.local(0, slot);
+                let declared = &mut self.blocks.last_mut().unwrap().1;
+                if slot == *declared {
+                    *declared += 1;
+                    self.line(&format!("Value {local} = {value};"));
+                    // locals may never be read, which C compilers warn about
+                    self.line(&format!("(void){local};"));
+                } else {
+                    // redefining a variable of the same block overwrites it
+                    self.line(&format!("{local} = {value};"));
+                }
+            }
+            _ => {
+                let global = self.global(&variable.name.lexeme);
+                self.line(&format!("lox_define_global({global}, {value});"));
+            }
+        }
+    }
+
+    fn visit_block(&mut self, block: &BlockStatement) -> Self::Return {
+        self.line("{");
+        self.indent_lvl += 1;
+        self.blocks.push((self.next_block, 0));
+        self.next_block += 1;
+        for statement in block.statements.iter() {
+            statement.accept(self);
+        }
+        self.blocks.pop();
+        self.indent_lvl -= 1;
+        self.line("}");
+    }
+
+    fn visit_error(&mut self, _error: &ErrorStatement) -> Self::Return {
+        unreachable!("programs which failed to parse are never transpiled")
+    }
+}
+
+/// Render a C string literal holding the given string, escaping every byte that isn't printable
+/// ASCII, as well as `?` which could start a trigraph
+fn c_string(s: &str) -> String {
+    let mut literal = String::with_capacity(s.len() + 2);
+    literal.push('"');
+    for byte in s.bytes() {
+        match byte {
+            b'"' | b'\\' | b'?' => {
+                literal.push('\\');
+                literal.push(byte as char);
+            }
+            b' '..=b'~' => literal.push(byte as char),
+            _ => write!(literal, "\\{byte:03o}").unwrap(),
+        }
+    }
+    literal.push('"');
+    literal
+}
+
+#[cfg(test)]
+mod test {
+    use super::{c_string, CTranspiler};
+    use crate::{ast::Arena, parser::Parser, scanner::Scanner};
+
+    fn main_function(source: &str) -> String {
+        let tokens = Scanner::new(source).scan_tokens().unwrap();
+        let arena = Arena::new();
+        let statements = Parser::new(tokens, &arena).parse().unwrap();
+        let output = CTranspiler::transpile(&statements);
+        let start = output
+            .rfind("\nstatic LoxGlobal")
+            .unwrap_or(output.rfind("\nint main").unwrap());
+        output[start + 1..].to_owned()
+    }
+
+    #[test]
+    fn test_transpile() {
+        assert_eq!(
+            main_function("var a = 1; { var b = a + 2; var b = -b; print b; a = b; }"),
+            r#"static LoxGlobal globals[] = {
+    LOX_GLOBAL("a"),
+};
+
+int main(void) {
+    Value t0 = lox_number(1.0);
+    lox_define_global(&globals[0], t0);
+    {
+        Value t1 = lox_get_global(&globals[0]);
+        Value t2 = lox_number(2.0);
+        Value t3 = lox_add(t1, t2);
+        Value b0_0 = t3;
+        (void)b0_0;
+        Value t4 = b0_0;
+        Value t5 = lox_negate(t4);
+        b0_0 = t5;
+        Value t6 = b0_0;
+        lox_print(t6);
+        Value t7 = b0_0;
+        lox_undefined_assignment("a");
+        (void)t7;
+    }
+    return 0;
+}
+"#
+        );
+    }
+
+    #[test]
+    fn test_c_string() {
+        assert_eq!(
+            c_string("a\"b\\c??=\n\u{e9}"),
+            r#""a\"b\\c\?\?=\012\303\251""#
+        );
+    }
+}
@@ -0,0 +1,297 @@
+/* Runtime of the Lox programs transpiled to C by rox, reproducing the semantics of its
+ * tree-walking interpreter. */
+#include <math.h>
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+
+typedef enum { LOX_NIL, LOX_BOOLEAN, LOX_NUMBER, LOX_STRING } LoxType;
+
+/* Strings are immutable and never freed: without loops nor functions, a program allocates at
+ * most one string per expression it contains. */
+typedef struct {
+    size_t len;
+    char chars[];
+} LoxString;
+
+typedef struct {
+    LoxType type;
+    union {
+        bool boolean;
+        double number;
+        const LoxString *string;
+    } as;
+} Value;
+
+typedef struct {
+    const char *name;
+    bool defined;
+    Value value;
+} LoxGlobal;
+
+static const Value LOX_NIL_VALUE = {LOX_NIL, {.boolean = false}};
+
+/* Entry of the table of globals, undefined until its variable is declared */
+#define LOX_GLOBAL(name) {name, false, {LOX_NIL, {.boolean = false}}}
+
+/* Report a runtime error like the interpreter does, then stop the program */
+static inline void lox_error(const char *kind, const char *format, ...) {
+    va_list args;
+    fflush(stdout);
+    fprintf(stderr, "%s: ", kind);
+    va_start(args, format);
+    vfprintf(stderr, format, args);
+    va_end(args);
+    fputc('\n', stderr);
+    exit(70);
+}
+
+static inline Value lox_boolean(bool v) {
+    Value value = {LOX_BOOLEAN, {.boolean = v}};
+    return value;
+}
+
+static inline Value lox_number(double v) {
+    Value value = {LOX_NUMBER, {.number = v}};
+    return value;
+}
+
+static inline Value lox_number_bits(unsigned long long bits) {
+    double v;
+    memcpy(&v, &bits, sizeof v);
+    return lox_number(v);
+}
+
+static inline LoxString *lox_alloc_string(size_t len) {
+    LoxString *string = malloc(sizeof(LoxString) + len);
+    if (string == NULL) {
+        fputs("Out of memory\n", stderr);
+        exit(70);
+    }
+    string->len = len;
+    return string;
+}
+
+static inline Value lox_string(const char *chars, size_t len) {
+    LoxString *string = lox_alloc_string(len);
+    memcpy(string->chars, chars, len);
+    Value value = {LOX_STRING, {.string = string}};
+    return value;
+}
+
+static inline bool lox_is_truthy(Value v) {
+    switch (v.type) {
+    case LOX_NIL:
+        return false;
+    case LOX_BOOLEAN:
+        return v.as.boolean;
+    default:
+        return true;
+    }
+}
+
+static inline bool lox_equals(Value l, Value r) {
+    if (l.type != r.type) {
+        return false;
+    }
+    switch (l.type) {
+    case LOX_NIL:
+        return true;
+    case LOX_BOOLEAN:
+        return l.as.boolean == r.as.boolean;
+    case LOX_NUMBER:
+        return l.as.number == r.as.number;
+    default:
+        return l.as.string->len == r.as.string->len &&
+               memcmp(l.as.string->chars, r.as.string->chars, l.as.string->len) == 0;
+    }
+}
+
+static inline Value lox_negate(Value v) {
+    if (v.type != LOX_NUMBER) {
+        lox_error("TypeError", "Expected f64 after unary operator -");
+    }
+    return lox_number(-v.as.number);
+}
+
+static inline Value lox_not(Value v) {
+    return lox_boolean(!lox_is_truthy(v));
+}
+
+/* Check the operands of an arithmetic or comparison operator are numbers */
+static inline void lox_check_numbers(const char *op, Value l, Value r) {
+    if (l.type != LOX_NUMBER) {
+        lox_error("TypeError", "Left of %s binary should be a valid number", op);
+    }
+    if (r.type != LOX_NUMBER) {
+        lox_error("TypeError", "Right of %s binary should be a valid number", op);
+    }
+}
+
+static inline Value lox_add(Value l, Value r) {
+    if (l.type == LOX_NUMBER) {
+        if (r.type != LOX_NUMBER) {
+            lox_error("TypeError",
+                      "Right of + binary should be a valid number when left is a number");
+        }
+        return lox_number(l.as.number + r.as.number);
+    }
+    if (l.type == LOX_STRING) {
+        if (r.type != LOX_STRING) {
+            lox_error("TypeError",
+                      "Right of + binary should be a valid string when left is a string");
+        }
+        LoxString *string = lox_alloc_string(l.as.string->len + r.as.string->len);
+        memcpy(string->chars, l.as.string->chars, l.as.string->len);
+        memcpy(string->chars + l.as.string->len, r.as.string->chars, r.as.string->len);
+        Value value = {LOX_STRING, {.string = string}};
+        return value;
+    }
+    lox_error("TypeError",
+              "Cannot evaluate + operand, left expression should be a string or number");
+    return LOX_NIL_VALUE;
+}
+
+static inline Value lox_subtract(Value l, Value r) {
+    lox_check_numbers("-", l, r);
+    return lox_number(l.as.number - r.as.number);
+}
+
+static inline Value lox_multiply(Value l, Value r) {
+    lox_check_numbers("*", l, r);
+    return lox_number(l.as.number * r.as.number);
+}
+
+static inline Value lox_divide(Value l, Value r) {
+    lox_check_numbers("/", l, r);
+    return lox_number(l.as.number / r.as.number);
+}
+
+static inline Value lox_greater(Value l, Value r) {
+    lox_check_numbers(">", l, r);
+    return lox_boolean(l.as.number > r.as.number);
+}
+
+static inline Value lox_greater_equal(Value l, Value r) {
+    lox_check_numbers(">=", l, r);
+    return lox_boolean(l.as.number >= r.as.number);
+}
+
+static inline Value lox_less(Value l, Value r) {
+    lox_check_numbers("<", l, r);
+    return lox_boolean(l.as.number < r.as.number);
+}
+
+static inline Value lox_less_equal(Value l, Value r) {
+    lox_check_numbers("<=", l, r);
+    return lox_boolean(l.as.number <= r.as.number);
+}
+
+static inline Value lox_equal(Value l, Value r) {
+    return lox_boolean(lox_equals(l, r));
+}
+
+static inline Value lox_not_equal(Value l, Value r) {
+    return lox_boolean(!lox_equals(l, r));
+}
+
+static inline void lox_define_global(LoxGlobal *global, Value v) {
+    global->defined = true;
+    global->value = v;
+}
+
+static inline Value lox_get_global(const LoxGlobal *global) {
+    if (!global->defined) {
+        lox_error("RuntimeError", "Undefined variable %s", global->name);
+    }
+    return global->value;
+}
+
+static inline Value lox_set_global(LoxGlobal *global, Value v) {
+    if (!global->defined) {
+        lox_error("RuntimeError", "Undefined variable '%s'", global->name);
+    }
+    global->value = v;
+    return v;
+}
+
+static inline void lox_undefined_assignment(const char *name) {
+    lox_error("RuntimeError", "Undefined variable '%s'", name);
+}
+
+/* Print a number like Rust's `f64` `Display` does: the shortest digits reading back as the same
+ * number, never in scientific notation, and without a fractional part for integers. */
+static inline void lox_print_number(double v) {
+    if (isnan(v)) {
+        fputs("NaN", stdout);
+        return;
+    }
+    if (isinf(v)) {
+        fputs(v < 0 ? "-inf" : "inf", stdout);
+        return;
+    }
+    if (v == 0) {
+        fputs(signbit(v) ? "-0" : "0", stdout);
+        return;
+    }
+
+    char buffer[32];
+    for (int precision = 0; precision < 17; precision++) {
+        snprintf(buffer, sizeof buffer, "%.*e", precision, v);
+        if (strtod(buffer, NULL) == v) {
+            break;
+        }
+    }
+
+    /* buffer holds [-]d.ddde[+-]xx, split it into its digits and exponent */
+    char digits[32];
+    int digits_len = 0;
+    const char *c = buffer;
+    if (*c == '-') {
+        putchar('-');
+        c++;
+    }
+    for (; *c != 'e'; c++) {
+        if (*c != '.') {
+            digits[digits_len++] = *c;
+        }
+    }
+    int exponent = atoi(c + 1);
+
+    if (exponent < 0) {
+        fputs("0.", stdout);
+        for (int i = -1; i > exponent; i--) {
+            putchar('0');
+        }
+        fwrite(digits, 1, digits_len, stdout);
+    } else if (exponent >= digits_len - 1) {
+        fwrite(digits, 1, digits_len, stdout);
+        for (int i = digits_len - 1; i < exponent; i++) {
+            putchar('0');
+        }
+    } else {
+        fwrite(digits, 1, exponent + 1, stdout);
+        putchar('.');
+        fwrite(digits + exponent + 1, 1, digits_len - exponent - 1, stdout);
+    }
+}
+
+static inline void lox_print(Value v) {
+    switch (v.type) {
+    case LOX_NIL:
+        fputs("nil", stdout);
+        break;
+    case LOX_BOOLEAN:
+        fputs(v.as.boolean ? "true" : "false", stdout);
+        break;
+    case LOX_NUMBER:
+        lox_print_number(v.as.number);
+        break;
+    case LOX_STRING:
+        fwrite(v.as.string->chars, 1, v.as.string->len, stdout);
+        break;
+    }
+    putchar('\n');
+}
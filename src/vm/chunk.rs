@@ -0,0 +1,200 @@
+use std::fmt::Write;
+
+use crate::interpreter::{value::Value, EvaluatedExpr};
+
+/// Instructions of the virtual machine.
+///
+/// Each instruction is encoded as one byte, followed by its operands: `u16` operands are indices
+/// in the constant pool stored in little-endian order, `u8` operands are stack slots or counts.
+#[repr(u8)]
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum OpCode {
+    /// `u16` operand: push a constant
+    Constant,
+    Nil,
+    True,
+    False,
+    Pop,
+    /// `u8` operand: pop that many values
+    PopN,
+    /// `u16` operand, constant holding the name: pop a value into a new global variable
+    DefineGlobal,
+    /// `u16` operand, constant holding the name: push the value of a global variable
+    GetGlobal,
+    /// `u16` operand, constant holding the name: store the top of the stack in an existing global
+    SetGlobal,
+    /// `u8` operand: push the value of a local variable
+    GetLocal,
+    /// `u8` operand: store the top of the stack in a local variable
+    SetLocal,
+    Negate,
+    Not,
+    Add,
+    Subtract,
+    Multiply,
+    Divide,
+    Equal,
+    NotEqual,
+    Greater,
+    GreaterEqual,
+    Less,
+    LessEqual,
+    Print,
+    /// Pop a value and print its representation, for the expression statements typed in the REPL
+    Echo,
+    /// `u16` operand, constant holding the message: raise a runtime error
+    Fail,
+    Return,
+}
+
+impl OpCode {
+    const ALL: [OpCode; 27] = [
+        OpCode::Constant,
+        OpCode::Nil,
+        OpCode::True,
+        OpCode::False,
+        OpCode::Pop,
+        OpCode::PopN,
+        OpCode::DefineGlobal,
+        OpCode::GetGlobal,
+        OpCode::SetGlobal,
+        OpCode::GetLocal,
+        OpCode::SetLocal,
+        OpCode::Negate,
+        OpCode::Not,
+        OpCode::Add,
+        OpCode::Subtract,
+        OpCode::Multiply,
+        OpCode::Divide,
+        OpCode::Equal,
+        OpCode::NotEqual,
+        OpCode::Greater,
+        OpCode::GreaterEqual,
+        OpCode::Less,
+        OpCode::LessEqual,
+        OpCode::Print,
+        OpCode::Echo,
+        OpCode::Fail,
+        OpCode::Return,
+    ];
+
+    /// Decode an instruction, returns None for bytes which aren't a valid instruction
+    pub fn from_byte(byte: u8) -> Option<OpCode> {
+        Self::ALL.get(byte as usize).copied()
+    }
+
+    /// Size in bytes of the operands following the instruction
+    fn operands_len(self) -> usize {
+        match self {
+            OpCode::Constant
+            | OpCode::DefineGlobal
+            | OpCode::GetGlobal
+            | OpCode::SetGlobal
+            | OpCode::Fail => 2,
+            OpCode::PopN | OpCode::GetLocal | OpCode::SetLocal => 1,
+            _ => 0,
+        }
+    }
+}
+
+/// A compiled program: its bytecode, the constants it refers to, and the source lines of its
+/// instructions
+#[derive(Debug, Default)]
+pub struct Chunk {
+    pub code: Vec<u8>,
+    pub constants: Vec<Value>,
+    /// Run-length encoded line table: `(offset, line)` pairs, each instruction starting from
+    /// `offset` comes from `line` until the next pair
+    lines: Vec<(usize, usize)>,
+}
+
+impl Chunk {
+    /// Append an instruction coming from the given source line
+    pub fn write_op(&mut self, op: OpCode, line: usize) {
+        if self.lines.last().map(|(_, l)| *l) != Some(line) {
+            self.lines.push((self.code.len(), line));
+        }
+        self.code.push(op as u8);
+    }
+
+    pub fn write_u8(&mut self, operand: u8) {
+        self.code.push(operand);
+    }
+
+    pub fn write_u16(&mut self, operand: u16) {
+        self.code.extend_from_slice(&operand.to_le_bytes());
+    }
+
+    /// Add a constant to the pool, returns its index
+    pub fn add_constant(&mut self, value: EvaluatedExpr) -> usize {
+        if let Some(index) = self
+            .constants
+            .iter()
+            .position(|c| same_constant(&c.to_evaluated(), &value))
+        {
+            return index;
+        }
+        self.constants.push(value.into());
+        self.constants.len() - 1
+    }
+
+    pub fn read_u16(&self, offset: usize) -> u16 {
+        u16::from_le_bytes([self.code[offset], self.code[offset + 1]])
+    }
+
+    /// Source line of the instruction at the given offset
+    pub fn line_at(&self, offset: usize) -> usize {
+        let run = self.lines.partition_point(|(start, _)| *start <= offset);
+        self.lines[run - 1].1
+    }
+
+    /// Render a human readable listing of the chunk
+    pub fn disassemble(&self, name: &str) -> String {
+        let mut output = format!("== {name} ==\n");
+        let mut offset = 0;
+        while offset < self.code.len() {
+            offset = self.disassemble_instruction(&mut output, offset);
+        }
+        output
+    }
+
+    /// Render the instruction at the given offset, returns the offset of the next one
+    fn disassemble_instruction(&self, output: &mut String, offset: usize) -> usize {
+        let line = self.line_at(offset);
+        if offset > 0 && self.line_at(offset - 1) == line {
+            write!(output, "{offset:04}    | ").unwrap();
+        } else {
+            write!(output, "{offset:04} {line:4} ").unwrap();
+        }
+
+        let Some(op) = OpCode::from_byte(self.code[offset]) else {
+            writeln!(output, "Unknown opcode {}", self.code[offset]).unwrap();
+            return offset + 1;
+        };
+        let name = format!("{op:?}");
+        match op.operands_len() {
+            2 => {
+                let index = self.read_u16(offset + 1);
+                writeln!(
+                    output,
+                    "{name:<16} {index:4} '{}'",
+                    self.constants[index as usize]
+                )
+                .unwrap();
+            }
+            1 => writeln!(output, "{name:<16} {:4}", self.code[offset + 1]).unwrap(),
+            _ => writeln!(output, "{name}").unwrap(),
+        }
+        offset + 1 + op.operands_len()
+    }
+}
+
+/// Whether two constants can share a slot of the pool, unlike `==` this never merges numbers of
+/// different sign or NaN payload
+fn same_constant(a: &EvaluatedExpr, b: &EvaluatedExpr) -> bool {
+    match (a, b) {
+        (EvaluatedExpr::Number(a), EvaluatedExpr::Number(b)) => a.to_bits() == b.to_bits(),
+        (EvaluatedExpr::String(a), EvaluatedExpr::String(b)) => a == b,
+        _ => false,
+    }
+}
@@ -0,0 +1,376 @@
+use std::rc::Rc;
+
+use super::{
+    chunk::{Chunk, OpCode},
+    error::{CompilerError, CompilerResult},
+};
+use crate::{
+    ast::{
+        expression::{Assign, Binary, ErrorExpr, Grouping, Literal, LiteralValue, Unary, Variable},
+        statement::{
+            BlockStatement,
+            ErrorStatement,
+            ExpressionStatement,
+            PrintStatement,
+            VariableStatement,
+        },
+        visitor::{ExprVisitor, StatementVisitor},
+        Statement,
+    },
+    interpreter::EvaluatedExpr,
+    token::{Token, TokenType},
+};
+
+/// A local variable living in a stack slot
+struct Local {
+    name: Rc<str>,
+    /// depth of the block the variable is declared in, 1 for the outermost block
+    depth: usize,
+}
+
+/// Compiles an AST into a chunk of bytecode for the virtual machine.
+///
+/// Variables declared outside of any block are globals looked up by name at runtime, the ones
+/// declared in blocks are resolved at compile time to the stack slot holding them. Scoping follows
+/// the tree-walking interpreter: reads look through every enclosing block then the globals, while
+/// assignments only reach the variables of the innermost block (or the globals at top level).
+pub struct Compiler {
+    chunk: Chunk,
+    locals: Vec<Local>,
+    scope_depth: usize,
+    /// Whether the values of the expression statements which aren't in a block are echoed
+    echo: bool,
+}
+
+impl Compiler {
+    /// Compile a whole program into a chunk
+    pub fn compile(statements: &[Statement]) -> CompilerResult<Chunk> {
+        Self::compile_program(statements, false)
+    }
+
+    /// Compile a program typed in the REPL, echoing the [`EvaluatedExpr::repr`] of the value of
+    /// its expression statements which aren't in a block
+    pub fn compile_echoing(statements: &[Statement]) -> CompilerResult<Chunk> {
+        Self::compile_program(statements, true)
+    }
+
+    fn compile_program(statements: &[Statement], echo: bool) -> CompilerResult<Chunk> {
+        let mut compiler = Compiler {
+            chunk: Chunk::default(),
+            locals: Vec::new(),
+            scope_depth: 0,
+            echo,
+        };
+        let mut last_line = 0;
+        for statement in statements {
+            statement.accept(&mut compiler)?;
+            last_line = statement.span().end_line;
+        }
+        compiler.chunk.write_op(OpCode::Return, last_line);
+        Ok(compiler.chunk)
+    }
+
+    fn emit_constant_op(
+        &mut self,
+        op: OpCode,
+        value: EvaluatedExpr,
+        line: usize,
+    ) -> CompilerResult<()> {
+        let index = self.chunk.add_constant(value);
+        let index = u16::try_from(index)
+            .map_err(|_| CompilerError::new(line, "Too many constants in one chunk".into()))?;
+        self.chunk.write_op(op, line);
+        self.chunk.write_u16(index);
+        Ok(())
+    }
+
+    fn emit_slot_op(&mut self, op: OpCode, slot: usize, line: usize) {
+        self.chunk.write_op(op, line);
+        // slots are bounded when declaring locals
+        self.chunk.write_u8(slot as u8);
+    }
+
+    /// Slot of the innermost local variable with the given name, declared in a block at least as
+    /// deep as `min_depth`
+    fn resolve_local(&self, name: &Token, min_depth: usize) -> Option<usize> {
+        self.locals
+            .iter()
+            .rposition(|local| local.depth >= min_depth && local.name == name.lexeme)
+    }
+
+    fn end_scope(&mut self, line: usize) {
+        let mut popped = 0;
+        while self
+            .locals
+            .last()
+            .map_or(false, |local| local.depth == self.scope_depth)
+        {
+            self.locals.pop();
+            popped += 1;
+        }
+        // a scope can hold one more local than an operand counts
+        while popped > 0 {
+            let count = popped.min(u8::MAX as usize);
+            self.emit_slot_op(OpCode::PopN, count, line);
+            popped -= count;
+        }
+        self.scope_depth -= 1;
+    }
+}
+
+impl ExprVisitor for Compiler {
+    type Return = CompilerResult<()>;
+
+    fn visit_unary(&mut self, unary: &Unary) -> Self::Return {
+        unary.expr.accept(self)?;
+        let op = match unary.op.token_type {
+            TokenType::Minus => OpCode::Negate,
+            TokenType::Bang => OpCode::Not,
+            t => {
+                return Err(CompilerError::new(
+                    unary.op.line,
+                    format!("Operand {t:?} not supported in unary expression"),
+                ))
+            }
+        };
+        self.chunk.write_op(op, unary.op.span.start_line);
+        Ok(())
+    }
+
+    fn visit_binary(&mut self, binary: &Binary) -> Self::Return {
+        binary.left.accept(self)?;
+        binary.right.accept(self)?;
+        let op = match binary.op.token_type {
+            TokenType::Plus => OpCode::Add,
+            TokenType::Minus => OpCode::Subtract,
+            TokenType::Star => OpCode::Multiply,
+            TokenType::Slash => OpCode::Divide,
+            TokenType::EqualEqual => OpCode::Equal,
+            TokenType::BangEqual => OpCode::NotEqual,
+            TokenType::Greater => OpCode::Greater,
+            TokenType::GreaterEqual => OpCode::GreaterEqual,
+            TokenType::Less => OpCode::Less,
+            TokenType::LessEqual => OpCode::LessEqual,
+            t => {
+                return Err(CompilerError::new(
+                    binary.op.line,
+                    format!("Operand {t:?} not supported in binary expression"),
+                ))
+            }
+        };
+        self.chunk.write_op(op, binary.op.span.start_line);
+        Ok(())
+    }
+
+    fn visit_grouping(&mut self, grouping: &Grouping) -> Self::Return {
+        grouping.expr.accept(self)
+    }
+
+    fn visit_literal(&mut self, literal: &Literal) -> Self::Return {
+        let line = literal.span.start_line;
+        match &literal.value {
+            LiteralValue::Nil => self.chunk.write_op(OpCode::Nil, line),
+            LiteralValue::Boolean(true) => self.chunk.write_op(OpCode::True, line),
+            LiteralValue::Boolean(false) => self.chunk.write_op(OpCode::False, line),
+            value => self.emit_constant_op(OpCode::Constant, value.into(), line)?,
+        }
+        Ok(())
+    }
+
+    fn visit_variable(&mut self, variable: &Variable) -> Self::Return {
+        let line = variable.span.start_line;
+        match self.resolve_local(&variable.name, 1) {
+            Some(slot) => self.emit_slot_op(OpCode::GetLocal, slot, line),
+            None => self.emit_constant_op(
+                OpCode::GetGlobal,
+                EvaluatedExpr::String(variable.name.lexeme.clone()),
+                line,
+            )?,
+        }
+        Ok(())
+    }
+
+    fn visit_assign(&mut self, assign: &Assign) -> Self::Return {
+        assign.value.accept(self)?;
+        let line = assign.span.start_line;
+        if self.scope_depth == 0 {
+            return self.emit_constant_op(
+                OpCode::SetGlobal,
+                EvaluatedExpr::String(assign.name.lexeme.clone()),
+                line,
+            );
+        }
+        match self.resolve_local(&assign.name, self.scope_depth) {
+            Some(slot) => self.emit_slot_op(OpCode::SetLocal, slot, line),
+            None => self.emit_constant_op(
+                OpCode::Fail,
+                EvaluatedExpr::String(
+                    format!("Undefined variable '{}'", assign.name.lexeme).into(),
+                ),
+                line,
+            )?,
+        }
+        Ok(())
+    }
+
+    fn visit_error(&mut self, error: &ErrorExpr) -> Self::Return {
+        Err(syntax_error(error.span.start_line))
+    }
+}
+
+impl StatementVisitor for Compiler {
+    type Return = CompilerResult<()>;
+
+    fn visit_print(&mut self, statement: &PrintStatement) -> Self::Return {
+        statement.expr.accept(self)?;
+        self.chunk
+            .write_op(OpCode::Print, statement.span.start_line);
+        Ok(())
+    }
+
+    fn visit_expression(&mut self, statement: &ExpressionStatement) -> Self::Return {
+        statement.expr.accept(self)?;
+        let op = if self.echo && self.scope_depth == 0 {
+            OpCode::Echo
+        } else {
+            OpCode::Pop
+        };
+        self.chunk.write_op(op, statement.span.end_line);
+        Ok(())
+    }
+
+    fn visit_variable(&mut self, variable: &VariableStatement) -> Self::Return {
+        let line = variable.span.start_line;
+        match variable.initializer.as_ref() {
+            Some(initializer) => initializer.accept(self)?,
+            None => self.chunk.write_op(OpCode::Nil, line),
+        }
+
+        if self.scope_depth == 0 {
+            return self.emit_constant_op(
+                OpCode::DefineGlobal,
+                EvaluatedExpr::String(variable.name.lexeme.clone()),
+                line,
+            );
+        }
+
+        if let Some(slot) = self.resolve_local(&variable.name, self.scope_depth) {
+            // redefining a variable of the same block overwrites it
+            self.emit_slot_op(OpCode::SetLocal, slot, line);
+            self.chunk.write_op(OpCode::Pop, line);
+        } else {
+            if self.locals.len() > u8::MAX as usize {
+                return Err(CompilerError::new(
+                    line,
+                    "Too many local variables in scope".into(),
+                ));
+            }
+            // the value of the initializer stays on the stack, in the slot of the variable
+            self.locals.push(Local {
+                name: variable.name.lexeme.clone(),
+                depth: self.scope_depth,
+            });
+        }
+        Ok(())
+    }
+
+    fn visit_block(&mut self, block: &BlockStatement) -> Self::Return {
+        self.scope_depth += 1;
+        for statement in block.statements.iter() {
+            statement.accept(self)?;
+        }
+        self.end_scope(block.span.end_line);
+        Ok(())
+    }
+
+    fn visit_error(&mut self, error: &ErrorStatement) -> Self::Return {
+        Err(syntax_error(error.span.start_line))
+    }
+}
+
+fn syntax_error(line: usize) -> CompilerError {
+    CompilerError::new(line, "Cannot compile code that failed to parse".into())
+}
+
+#[cfg(test)]
+mod test {
+    use super::Compiler;
+    use crate::{ast::Arena, parser::Parser, scanner::Scanner};
+
+    fn disassemble(source: &str) -> String {
+        let tokens = Scanner::new(source).scan_tokens().unwrap();
+        let arena = Arena::new();
+        let statements = Parser::new(tokens, &arena).parse().unwrap();
+        Compiler::compile(&statements).unwrap().disassemble("test")
+    }
+
+    #[test]
+    fn test_globals_and_constants() {
+        assert_eq!(
+            disassemble("var a = 1;\nprint a + 1;"),
+            "== test ==
+0000    0 Constant            0 '1'
+0003    | DefineGlobal        1 'a'
+0006    1 GetGlobal           1 'a'
+0009    | Constant            0 '1'
+0012    | Add
+0013    | Print
+0014    | Return
+"
+        );
+    }
+
+    #[test]
+    fn test_locals() {
+        assert_eq!(
+            disassemble("{ var a; var b = -a; a = b; var a = nil; }"),
+            "== test ==
+0000    0 Nil
+0001    | GetLocal            0
+0003    | Negate
+0004    | GetLocal            1
+0006    | SetLocal            0
+0008    | Pop
+0009    | Nil
+0010    | SetLocal            0
+0012    | Pop
+0013    | PopN                2
+0015    | Return
+"
+        );
+    }
+
+    #[test]
+    fn test_assign_outside_innermost_block_fails() {
+        assert_eq!(
+            disassemble("{ var a; { a = true; } }"),
+            "== test ==
+0000    0 Nil
+0001    | True
+0002    | Fail                0 'Undefined variable 'a''
+0005    | Pop
+0006    | PopN                1
+0008    | Return
+"
+        );
+    }
+
+    #[test]
+    fn test_echo_top_level_expressions() {
+        let tokens = Scanner::new("1;\n{ 2; }").scan_tokens().unwrap();
+        let arena = Arena::new();
+        let statements = Parser::new(tokens, &arena).parse().unwrap();
+        assert_eq!(
+            Compiler::compile_echoing(&statements)
+                .unwrap()
+                .disassemble("test"),
+            "== test ==
+0000    0 Constant            0 '1'
+0003    | Echo
+0004    1 Constant            1 '2'
+0007    | Pop
+0008    | Return
+"
+        );
+    }
+}
@@ -0,0 +1,25 @@
+use std::fmt::Display;
+
+use thiserror::Error;
+
+#[derive(Error, Debug, PartialEq)]
+pub struct CompilerError {
+    line: usize,
+    msg: String,
+}
+
+impl CompilerError {
+    pub fn new(line: usize, msg: String) -> Self {
+        Self { line, msg }
+    }
+}
+
+impl Display for CompilerError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Compiling Error - line {}: {}", self.line, self.msg)?;
+
+        Ok(())
+    }
+}
+
+pub type CompilerResult<T> = Result<T, CompilerError>;
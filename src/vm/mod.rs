@@ -0,0 +1,192 @@
+pub mod chunk;
+pub mod compiler;
+pub mod error;
+
+use std::{collections::HashMap, fmt, rc::Rc};
+
+use self::chunk::{Chunk, OpCode};
+use crate::{
+    interpreter::{
+        environment::fmt_globals,
+        error::{InterpreterError, InterpreterResult},
+        interrupt::Interrupt,
+        operators,
+        value::Value,
+    },
+    token::TokenType,
+};
+
+/// Stack based virtual machine executing the chunks produced by the [`compiler::Compiler`].
+///
+/// Globals are kept from one chunk to the next, so that a REPL session can run its inputs one
+/// after the other.
+#[derive(Default)]
+pub struct Vm {
+    stack: Vec<Value>,
+    globals: HashMap<Rc<str>, Value>,
+    interrupt: Interrupt,
+}
+
+impl Vm {
+    /// Request to stop the running chunk
+    pub fn interrupt(&self) -> &Interrupt {
+        &self.interrupt
+    }
+
+    pub fn global_names(&self) -> impl Iterator<Item = &Rc<str>> {
+        self.globals.keys()
+    }
+
+    pub fn run(&mut self, chunk: &Chunk) -> InterpreterResult<()> {
+        let result = self.execute(chunk);
+        // a failed chunk can leave temporaries and locals behind
+        self.stack.clear();
+        result
+    }
+
+    fn execute(&mut self, chunk: &Chunk) -> InterpreterResult<()> {
+        let mut ip = 0;
+        loop {
+            self.interrupt.check()?;
+            let op = OpCode::from_byte(chunk.code[ip]).expect("chunk should hold valid bytecode");
+            ip += 1;
+
+            match op {
+                OpCode::Constant => {
+                    let value = Self::read_constant(chunk, &mut ip).clone();
+                    self.stack.push(value);
+                }
+                OpCode::Nil => self.stack.push(Value::nil()),
+                OpCode::True => self.stack.push(Value::boolean(true)),
+                OpCode::False => self.stack.push(Value::boolean(false)),
+                OpCode::Pop => {
+                    self.pop();
+                }
+                OpCode::PopN => {
+                    let count = Self::read_u8(chunk, &mut ip) as usize;
+                    self.stack.truncate(self.stack.len() - count);
+                }
+                OpCode::DefineGlobal => {
+                    let name = Self::read_name(chunk, &mut ip);
+                    let value = self.pop();
+                    self.globals.insert(name.clone(), value);
+                }
+                OpCode::GetGlobal => {
+                    let name = Self::read_name(chunk, &mut ip);
+                    let value = self.globals.get(name).cloned().ok_or_else(|| {
+                        InterpreterError::RuntimeError(format!("Undefined variable {name}"))
+                    })?;
+                    self.stack.push(value);
+                }
+                OpCode::SetGlobal => {
+                    let name = Self::read_name(chunk, &mut ip);
+                    let value = self.peek().clone();
+                    match self.globals.get_mut(name) {
+                        Some(global) => *global = value,
+                        None => {
+                            return Err(InterpreterError::RuntimeError(format!(
+                                "Undefined variable '{name}'"
+                            )))
+                        }
+                    }
+                }
+                OpCode::GetLocal => {
+                    let slot = Self::read_u8(chunk, &mut ip) as usize;
+                    self.stack.push(self.stack[slot].clone());
+                }
+                OpCode::SetLocal => {
+                    let slot = Self::read_u8(chunk, &mut ip) as usize;
+                    self.stack[slot] = self.peek().clone();
+                }
+                OpCode::Negate => self.unary(TokenType::Minus)?,
+                OpCode::Not => self.unary(TokenType::Bang)?,
+                OpCode::Add => self.binary(TokenType::Plus)?,
+                OpCode::Subtract => self.binary(TokenType::Minus)?,
+                OpCode::Multiply => self.binary(TokenType::Star)?,
+                OpCode::Divide => self.binary(TokenType::Slash)?,
+                OpCode::Equal => self.binary(TokenType::EqualEqual)?,
+                OpCode::NotEqual => self.binary(TokenType::BangEqual)?,
+                OpCode::Greater => self.binary(TokenType::Greater)?,
+                OpCode::GreaterEqual => self.binary(TokenType::GreaterEqual)?,
+                OpCode::Less => self.binary(TokenType::Less)?,
+                OpCode::LessEqual => self.binary(TokenType::LessEqual)?,
+                OpCode::Print => println!("{}", self.pop()),
+                OpCode::Echo => println!("{}", self.pop().to_evaluated().repr()),
+                OpCode::Fail => {
+                    let msg = Self::read_name(chunk, &mut ip);
+                    return Err(InterpreterError::RuntimeError(msg.to_string()));
+                }
+                OpCode::Return => return Ok(()),
+            }
+        }
+    }
+
+    /// Apply an unary operator with the same semantics as the tree-walking interpreter
+    fn unary(&mut self, op: TokenType) -> InterpreterResult<()> {
+        let right = self.pop().to_evaluated();
+        self.stack.push(operators::unary(op, right)?.into());
+        Ok(())
+    }
+
+    /// Apply a binary operator with the same semantics as the tree-walking interpreter
+    fn binary(&mut self, op: TokenType) -> InterpreterResult<()> {
+        let right = self.pop();
+        let left = self.pop();
+        // arithmetic and comparisons of numbers don't need to unpack the values
+        if let (Some(l), Some(r)) = (left.as_number(), right.as_number()) {
+            let result = match op {
+                TokenType::Plus => Some(Value::number(l + r)),
+                TokenType::Minus => Some(Value::number(l - r)),
+                TokenType::Star => Some(Value::number(l * r)),
+                TokenType::Slash => Some(Value::number(l / r)),
+                TokenType::Greater => Some(Value::boolean(l > r)),
+                TokenType::GreaterEqual => Some(Value::boolean(l >= r)),
+                TokenType::Less => Some(Value::boolean(l < r)),
+                TokenType::LessEqual => Some(Value::boolean(l <= r)),
+                _ => None,
+            };
+            if let Some(result) = result {
+                self.stack.push(result);
+                return Ok(());
+            }
+        }
+        let result = operators::binary(op, left.to_evaluated(), right.to_evaluated())?;
+        self.stack.push(result.into());
+        Ok(())
+    }
+
+    fn pop(&mut self) -> Value {
+        self.stack.pop().expect("stack shouldn't underflow")
+    }
+
+    fn peek(&self) -> &Value {
+        self.stack.last().expect("stack shouldn't be empty")
+    }
+
+    fn read_u8(chunk: &Chunk, ip: &mut usize) -> u8 {
+        *ip += 1;
+        chunk.code[*ip - 1]
+    }
+
+    fn read_constant<'c>(chunk: &'c Chunk, ip: &mut usize) -> &'c Value {
+        let index = chunk.read_u16(*ip) as usize;
+        *ip += 2;
+        &chunk.constants[index]
+    }
+
+    /// Read a constant which is known to be a string, such as a variable name
+    fn read_name<'c>(chunk: &'c Chunk, ip: &mut usize) -> &'c Rc<str> {
+        let constant = Self::read_constant(chunk, ip);
+        match constant.as_str() {
+            Some(name) => name,
+            None => panic!("expected a string constant, got {constant:?}"),
+        }
+    }
+}
+
+impl fmt::Display for Vm {
+    /// The globals, one per line
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt_globals(&self.globals, f)
+    }
+}
@@ -0,0 +1,126 @@
+use std::{
+    io::{self, Write},
+    path::{Path, PathBuf},
+    sync::mpsc::{self, RecvTimeoutError},
+    time::{Duration, SystemTime},
+};
+
+use camino::{Utf8Path, Utf8PathBuf};
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+
+use crate::error::FacingRoxResult;
+
+/// How long the changes have to stop for before running the file again, as saving a file can take
+/// several writes
+const DEBOUNCE: Duration = Duration::from_millis(200);
+
+/// Run `file` with `run`, which returns its exit status, then again whenever it changes or
+/// something under `dirs` does, until the process is killed. A header with the time is printed
+/// before and after each run, and the screen is cleared before it if `clear` is set.
+pub fn watch(
+    file: &Utf8Path,
+    dirs: &[Utf8PathBuf],
+    clear: bool,
+    mut run: impl FnMut() -> i32,
+) -> FacingRoxResult<()> {
+    let file = file.canonicalize()?;
+    let dirs = dirs
+        .iter()
+        .map(|dir| dir.canonicalize())
+        .collect::<io::Result<Vec<_>>>()?;
+
+    let (sender, receiver) = mpsc::channel();
+    let mut watcher: RecommendedWatcher = notify::recommended_watcher(sender)?;
+    // editors often save by replacing the file, which would end a watch on the file itself
+    let parent = file.parent().unwrap_or(Path::new("/"));
+    watcher.watch(parent, RecursiveMode::NonRecursive)?;
+    for dir in dirs.iter() {
+        watcher.watch(dir, RecursiveMode::Recursive)?;
+    }
+
+    loop {
+        if clear {
+            print!("\x1b[2J\x1b[H");
+        }
+        eprintln!("[{}] Running {}", timestamp(), file.display());
+        let status = run();
+        io::stdout().flush()?;
+        eprintln!("[{}] Exited with status {}", timestamp(), status);
+
+        // wait for a change, then for the changes to stop
+        loop {
+            let event = receiver.recv().expect("the watcher outlives the loop")?;
+            if is_change(&event, &file, &dirs) {
+                break;
+            }
+        }
+        loop {
+            match receiver.recv_timeout(DEBOUNCE) {
+                Ok(event) => {
+                    event?;
+                }
+                Err(RecvTimeoutError::Timeout) => break,
+                Err(RecvTimeoutError::Disconnected) => {
+                    unreachable!("the watcher outlives the loop")
+                }
+            }
+        }
+    }
+}
+
+/// Whether the event changes `file` or something under `dirs`, rather than reading them
+fn is_change(event: &Event, file: &Path, dirs: &[PathBuf]) -> bool {
+    matches!(
+        event.kind,
+        EventKind::Any | EventKind::Create(_) | EventKind::Modify(_) | EventKind::Remove(_)
+    ) && event
+        .paths
+        .iter()
+        .any(|path| path == file || dirs.iter().any(|dir| path.starts_with(dir)))
+}
+
+/// Current UTC time, to the second
+fn timestamp() -> humantime::Rfc3339Timestamp {
+    humantime::format_rfc3339_seconds(SystemTime::now())
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::PathBuf;
+
+    use notify::{
+        event::{AccessKind, CreateKind, ModifyKind},
+        Event,
+        EventKind,
+    };
+
+    use super::is_change;
+
+    #[test]
+    fn test_is_change() {
+        let file = PathBuf::from("/project/main.rox");
+        let dirs = vec![PathBuf::from("/project/lib")];
+        let event = |kind, path: &str| Event::new(kind).add_path(PathBuf::from(path));
+        let modify = EventKind::Modify(ModifyKind::Any);
+
+        assert!(is_change(&event(modify, "/project/main.rox"), &file, &dirs));
+        assert!(is_change(
+            &event(
+                EventKind::Create(CreateKind::File),
+                "/project/lib/util/a.rox"
+            ),
+            &file,
+            &dirs
+        ));
+        assert!(!is_change(
+            &event(modify, "/project/other.rox"),
+            &file,
+            &dirs
+        ));
+        assert!(!is_change(
+            &event(EventKind::Access(AccessKind::Any), "/project/main.rox"),
+            &file,
+            &dirs
+        ));
+    }
+}
@@ -0,0 +1,55 @@
+use std::fs;
+
+use assert_cmd::{prelude::*, Command};
+
+#[test]
+fn test_emitted_ast_json_runs_like_source() {
+    let emitted = Command::cargo_bin("rox")
+        .expect("Cannot find cargo binary target rox")
+        .args(["--emit=ast-json", "tests/data/blocks.rox"])
+        .output()
+        .expect("rox binary invokation failed");
+    let json_path = std::env::temp_dir().join("rox_test_blocks.json");
+    fs::write(&json_path, &emitted.stdout).expect("Cannot write emitted AST");
+    emitted.assert().success().stderr("");
+
+    let cmd = Command::cargo_bin("rox")
+        .expect("Cannot find cargo binary target rox")
+        .arg("--input=ast-json")
+        .arg(&json_path)
+        .output()
+        .expect("rox binary invokation failed");
+    cmd.assert().success().stdout("9\nxy\n").stderr("");
+}
+
+#[test]
+fn test_duplicate_node_ids_are_rejected() {
+    let emitted = Command::cargo_bin("rox")
+        .expect("Cannot find cargo binary target rox")
+        .args(["--emit=ast-json", "-"])
+        .write_stdin("var g = 5; print g; { var a = 1; }")
+        .output()
+        .expect("rox binary invokation failed");
+    let json = String::from_utf8(emitted.stdout).expect("Emitted AST is not UTF-8");
+    let json = json
+        .lines()
+        .map(|line| match line.trim_start().strip_prefix("\"id\": ") {
+            Some(_) => format!(
+                "{}\"id\": 7,",
+                &line[..line.len() - line.trim_start().len()]
+            ),
+            None => line.to_string(),
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    let cmd = Command::cargo_bin("rox")
+        .expect("Cannot find cargo binary target rox")
+        .args(["--input=ast-json", "-"])
+        .write_stdin(json)
+        .output()
+        .expect("rox binary invokation failed");
+    let stderr = String::from_utf8_lossy(&cmd.stderr).into_owned();
+    cmd.assert().code(65).stdout("");
+    assert!(stderr.contains("duplicate node id 7"), "{stderr}");
+}
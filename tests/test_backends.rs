@@ -0,0 +1,86 @@
+use std::fs;
+
+use assert_cmd::{prelude::*, Command};
+
+fn run(backend: &str, path: &str) -> std::process::Output {
+    Command::cargo_bin("rox")
+        .expect("Cannot find cargo binary target rox")
+        .args(["--no-cache", &format!("--backend={backend}"), path])
+        .output()
+        .expect("rox binary invokation failed")
+}
+
+#[test]
+fn test_backends_behave_the_same() {
+    for entry in fs::read_dir("tests/data").expect("Cannot list test programs") {
+        let path = entry.expect("Cannot read test program").path();
+        let path = path.to_str().unwrap();
+
+        let tree = run("tree", path);
+        let vm = run("vm", path);
+        assert_eq!(tree.status, vm.status, "{path}");
+        assert_eq!(
+            String::from_utf8_lossy(&tree.stdout),
+            String::from_utf8_lossy(&vm.stdout),
+            "{path}"
+        );
+        assert_eq!(
+            String::from_utf8_lossy(&tree.stderr),
+            String::from_utf8_lossy(&vm.stderr),
+            "{path}"
+        );
+    }
+}
+
+#[test]
+fn test_vm_scopes() {
+    run("vm", "tests/data/scopes.rox")
+        .assert()
+        .success()
+        .stdout("outer a, inner b\ninner b again\nouter a!\nglobal a, global b\ntrue\n")
+        .stderr("RuntimeError: Undefined variable 'c'\n");
+}
+
+#[test]
+fn test_scope_with_most_locals_is_popped() {
+    let mut source = String::from("var g = \"global\";\n{\n");
+    for i in 0..256 {
+        source.push_str(&format!("  var v{i} = {i};\n"));
+    }
+    source.push_str("}\n{ var x = \"inner\"; print g; print x; }\n");
+    let path = std::env::temp_dir().join(format!("rox_most_locals_{}.rox", std::process::id()));
+    fs::write(&path, source).unwrap();
+    let path = path.to_str().unwrap();
+
+    let tree = run("tree", path);
+    let vm = run("vm", path);
+    fs::remove_file(path).unwrap();
+    tree.assert().success().stdout("global\ninner\n").stderr("");
+    vm.assert().success().stdout("global\ninner\n").stderr("");
+}
+
+#[test]
+fn test_disassemble() {
+    Command::cargo_bin("rox")
+        .expect("Cannot find cargo binary target rox")
+        .args(["--disassemble", "tests/data/blocks.rox"])
+        .assert()
+        .success()
+        .stdout(
+            "== tests/data/blocks.rox ==
+0000    0 Constant            0 '1'
+0003    | DefineGlobal        1 'a'
+0006    2 GetGlobal           1 'a'
+0009    | Constant            2 '2'
+0012    | Add
+0013    3 GetLocal            0
+0015    | Constant            3 '3'
+0018    | Multiply
+0019    | Print
+0020    4 PopN                1
+0022    5 Constant            4 'xy'
+0025    | Print
+0026    | Return
+",
+        );
+}
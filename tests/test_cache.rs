@@ -0,0 +1,107 @@
+use std::{fs, path::PathBuf, process};
+
+use assert_cmd::{prelude::*, Command};
+
+/// Fresh cache directory and program file for a test
+fn setup(name: &str) -> (PathBuf, PathBuf) {
+    let dir = std::env::temp_dir().join(format!("rox_test_cache_{name}_{}", process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).expect("Cannot create temporary directory");
+    (dir.join("cache"), dir.join("program.rox"))
+}
+
+fn run(cache: &PathBuf, program: &PathBuf, args: &[&str]) -> process::Output {
+    Command::cargo_bin("rox")
+        .expect("Cannot find cargo binary target rox")
+        .env("ROX_CACHE_DIR", cache)
+        .arg("-v")
+        .args(args)
+        .arg(program)
+        .output()
+        .expect("rox binary invokation failed")
+}
+
+fn loaded_from_cache(output: &process::Output) -> bool {
+    String::from_utf8_lossy(&output.stderr).contains("from the cache")
+}
+
+#[test]
+fn test_program_is_loaded_from_cache() {
+    let (cache, program) = setup("loaded");
+    fs::write(&program, "var a = 1; { var b = a + 1; print b; }").unwrap();
+
+    let first = run(&cache, &program, &[]);
+    assert!(!loaded_from_cache(&first));
+    first.assert().success().stdout("2\n");
+    assert_eq!(fs::read_dir(&cache).unwrap().count(), 1);
+
+    let second = run(&cache, &program, &[]);
+    assert!(loaded_from_cache(&second));
+    second.assert().success().stdout("2\n");
+}
+
+#[test]
+fn test_stale_program_is_parsed_again() {
+    let (cache, program) = setup("stale");
+    fs::write(&program, "print 1;").unwrap();
+    run(&cache, &program, &[]).assert().success().stdout("1\n");
+
+    fs::write(&program, "print 2;").unwrap();
+    let edited = run(&cache, &program, &[]);
+    assert!(!loaded_from_cache(&edited));
+    edited.assert().success().stdout("2\n");
+
+    // the optimization level is part of the key too
+    let unoptimized = run(&cache, &program, &["-O0"]);
+    assert!(!loaded_from_cache(&unoptimized));
+    unoptimized.assert().success().stdout("2\n");
+}
+
+#[test]
+fn test_corrupted_cache_is_ignored() {
+    let (cache, program) = setup("corrupted");
+    fs::write(&program, "print \"a\" + \"b\";").unwrap();
+    run(&cache, &program, &[]).assert().success();
+
+    for entry in fs::read_dir(&cache).unwrap() {
+        fs::write(entry.unwrap().path(), b"garbage").unwrap();
+    }
+    let output = run(&cache, &program, &[]);
+    assert!(!loaded_from_cache(&output));
+    output.assert().success().stdout("ab\n");
+}
+
+#[test]
+fn test_no_cache() {
+    let (cache, program) = setup("no_cache");
+    fs::write(&program, "print 1;").unwrap();
+
+    run(&cache, &program, &["--no-cache"])
+        .assert()
+        .success()
+        .stdout("1\n");
+    assert!(!cache.exists());
+}
+
+#[test]
+fn test_one_off_programs_are_not_cached() {
+    let (cache, _) = setup("one_off");
+    let rox = || {
+        let mut cmd = Command::cargo_bin("rox").expect("Cannot find cargo binary target rox");
+        cmd.env("ROX_CACHE_DIR", &cache);
+        cmd
+    };
+
+    rox()
+        .args(["-e", "print 1;"])
+        .assert()
+        .success()
+        .stdout("1\n");
+    rox()
+        .arg("-")
+        .write_stdin("print 2;")
+        .assert()
+        .success()
+        .stdout("2\n");
+    assert!(!cache.exists());
+}
@@ -0,0 +1,71 @@
+use std::{fs, process};
+
+use assert_cmd::{prelude::*, Command};
+
+#[test]
+fn test_check_valid_files() {
+    let cmd = Command::cargo_bin("rox")
+        .expect("Cannot find cargo binary target rox")
+        .args([
+            "check",
+            "tests/test.rox",
+            "tests/data/blocks.rox",
+            "tests/data/shebang.rox",
+        ])
+        .output()
+        .expect("rox binary invokation failed");
+    cmd.assert().success().stdout("").stderr("");
+}
+
+#[test]
+fn test_check_does_not_run_programs() {
+    // the program fails at runtime after printing, only the failure is reported
+    let cmd = Command::cargo_bin("rox")
+        .expect("Cannot find cargo binary target rox")
+        .args(["check", "tests/data/scopes.rox"])
+        .output()
+        .expect("rox binary invokation failed");
+    cmd.assert()
+        .code(65)
+        .stdout("")
+        .stderr("tests/data/scopes.rox: Static Error - line 19: Undefined variable 'c'\n");
+}
+
+#[test]
+fn test_check_directories() {
+    let dir = std::env::temp_dir().join(format!("rox_test_check_{}", process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(dir.join("nested")).expect("Cannot create temporary directory");
+    fs::write(dir.join("valid.rox"), "var a = 1;\nprint a;\n").expect("Cannot write file");
+    fs::write(
+        dir.join("nested").join("invalid.rox"),
+        "print (1;\nprint b;\n",
+    )
+    .expect("Cannot write file");
+    // only `.rox` files are looked for
+    fs::write(dir.join("notes.txt"), "print (;").expect("Cannot write file");
+
+    let cmd = Command::cargo_bin("rox")
+        .expect("Cannot find cargo binary target rox")
+        .arg("check")
+        .arg(&dir)
+        .output()
+        .expect("rox binary invokation failed");
+    let invalid = dir.join("nested").join("invalid.rox");
+    cmd.assert().code(65).stdout("").stderr(format!(
+        "{0}: Parsing Error - line 0 at ;: Expect ')' after expression.\n{0}: Static Error - line \
+         1: Undefined variable 'b'\n",
+        invalid.display()
+    ));
+    fs::remove_dir_all(&dir).ok();
+}
+
+#[test]
+fn test_check_missing_file() {
+    let cmd = Command::cargo_bin("rox")
+        .expect("Cannot find cargo binary target rox")
+        .args(["check", "tests/data/missing.rox"])
+        .output()
+        .expect("rox binary invokation failed");
+    cmd.assert().code(65).stdout("");
+}
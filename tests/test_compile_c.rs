@@ -0,0 +1,124 @@
+use std::{fs, path::Path, process};
+
+use assert_cmd::Command;
+
+/// Programs exercising the runtime errors of the interpreter, on top of those of `tests/data`
+const ERROR_PROGRAMS: &[(&str, &str)] = &[
+    ("undefined_read", "print 1;\nprint x;\n"),
+    ("undefined_assign", "print 1;\nx = 2;\n"),
+    ("inner_assign", "var x = 1;\n{ x = 2; }\n"),
+    ("negate_string", "print -\"a\";\n"),
+    ("add_mixed", "print 1 + \"a\";\n"),
+    ("add_nil", "print nil + nil;\n"),
+    ("compare_string", "print 1 < \"a\";\n"),
+];
+
+fn has_c_compiler() -> bool {
+    process::Command::new("cc")
+        .arg("--version")
+        .output()
+        .map(|output| output.status.success())
+        .unwrap_or(false)
+}
+
+/// Transpile the program to C, compile and run it
+fn run_compiled(path: &Path, name: &str) -> process::Output {
+    let dir = std::env::temp_dir().join(format!("rox_test_compile_c_{}", process::id()));
+    fs::create_dir_all(&dir).expect("Cannot create temporary directory");
+    let source = dir.join(format!("{name}.c"));
+    let binary = dir.join(name);
+
+    Command::cargo_bin("rox")
+        .expect("Cannot find cargo binary target rox")
+        .args(["compile", "--target=c", "-o"])
+        .arg(&source)
+        .arg(path)
+        .assert()
+        .success();
+    let compiled = process::Command::new("cc")
+        .args(["-std=c99", "-Wall", "-o"])
+        .arg(&binary)
+        .arg(&source)
+        .arg("-lm")
+        .output()
+        .expect("cc invokation failed");
+    assert!(
+        compiled.status.success() && compiled.stderr.is_empty(),
+        "{}",
+        String::from_utf8_lossy(&compiled.stderr)
+    );
+    process::Command::new(&binary)
+        .output()
+        .expect("compiled program invokation failed")
+}
+
+fn assert_same_as_interpreter(path: &Path, name: &str) {
+    let interpreted = Command::cargo_bin("rox")
+        .expect("Cannot find cargo binary target rox")
+        .arg("--no-cache")
+        .arg(path)
+        .output()
+        .expect("rox binary invokation failed");
+    let compiled = run_compiled(path, name);
+    // runtime errors are only reflected in the exit status of the compiled program
+    let expected_status = if interpreted.stderr.is_empty() { 0 } else { 70 };
+    assert!(interpreted.status.success(), "{name}");
+    assert_eq!(compiled.status.code(), Some(expected_status), "{name}");
+    assert_eq!(
+        String::from_utf8_lossy(&interpreted.stdout),
+        String::from_utf8_lossy(&compiled.stdout),
+        "{name}"
+    );
+    assert_eq!(
+        String::from_utf8_lossy(&interpreted.stderr),
+        String::from_utf8_lossy(&compiled.stderr),
+        "{name}"
+    );
+}
+
+#[test]
+fn test_compiled_programs_behave_like_interpreter() {
+    if !has_c_compiler() {
+        eprintln!("no C compiler found, skipping");
+        return;
+    }
+    for entry in fs::read_dir("tests/data").expect("Cannot list test programs") {
+        let path = entry.expect("Cannot read test program").path();
+        let name = path.file_stem().unwrap().to_str().unwrap().to_owned();
+        assert_same_as_interpreter(&path, &name);
+    }
+}
+
+#[test]
+fn test_compiled_runtime_errors() {
+    if !has_c_compiler() {
+        eprintln!("no C compiler found, skipping");
+        return;
+    }
+    let dir = std::env::temp_dir().join(format!("rox_test_compile_c_errors_{}", process::id()));
+    fs::create_dir_all(&dir).expect("Cannot create temporary directory");
+    for (name, program) in ERROR_PROGRAMS {
+        let path = dir.join(format!("{name}.rox"));
+        fs::write(&path, program).expect("Cannot write test program");
+        assert_same_as_interpreter(&path, name);
+    }
+}
+
+#[test]
+fn test_compiled_number_formatting() {
+    if !has_c_compiler() {
+        eprintln!("no C compiler found, skipping");
+        return;
+    }
+    let dir = std::env::temp_dir().join(format!("rox_test_compile_c_numbers_{}", process::id()));
+    fs::create_dir_all(&dir).expect("Cannot create temporary directory");
+    let path = dir.join("numbers.rox");
+    fs::write(
+        &path,
+        "print 1; print 0.1 + 0.2; print 1 / 3; print 100000000000000000000000; print \
+         0.000001234; print -2.5; print 1 / 0; print -1 / 0; print 0 / 0; print -0; print \"a\" + \
+         \"b\" == \"ab\"; print nil == false; print !nil;",
+    )
+    .expect("Cannot write test program");
+    assert_same_as_interpreter(&path, "numbers");
+}
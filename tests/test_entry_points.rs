@@ -0,0 +1,64 @@
+use assert_cmd::{prelude::*, Command};
+
+fn rox() -> Command {
+    let mut cmd = Command::cargo_bin("rox").expect("Cannot find cargo binary target rox");
+    cmd.arg("--no-cache");
+    cmd
+}
+
+#[test]
+fn test_eval() {
+    for backend in ["tree", "vm"] {
+        let cmd = rox()
+            .args(["--backend", backend, "-e", "var a = 1; print a + 2;"])
+            .output()
+            .expect("rox binary invokation failed");
+        cmd.assert().success().stdout("3\n").stderr("");
+    }
+
+    // every error is reported once
+    let cmd = rox()
+        .args(["--eval", "print ;\nvar = 1;"])
+        .output()
+        .expect("rox binary invokation failed");
+    cmd.assert().code(65).stdout("").stderr(
+        "Parsing Error - line 0 at ;: Expected expression\nParsing Error - line 1 at =: Expected \
+         variable name\n",
+    );
+}
+
+#[test]
+fn test_eval_conflicts_with_file() {
+    let cmd = rox()
+        .args(["-e", "print 1;", "tests/test.rox"])
+        .output()
+        .expect("rox binary invokation failed");
+    cmd.assert().failure().stdout("");
+}
+
+#[test]
+fn test_stdin() {
+    // the program is run as a whole, without the prompts of the REPL
+    let cmd = rox()
+        .arg("-")
+        .write_stdin("var a = \"piped\";\nprint a;\n")
+        .output()
+        .expect("rox binary invokation failed");
+    cmd.assert().success().stdout("piped\n").stderr("");
+
+    let cmd = rox()
+        .args(["--emit", "ast-json", "-"])
+        .write_stdin("print 1;")
+        .output()
+        .expect("rox binary invokation failed");
+    cmd.assert().success().stderr("");
+}
+
+#[test]
+fn test_shebang() {
+    let cmd = rox()
+        .arg("tests/data/shebang.rox")
+        .output()
+        .expect("rox binary invokation failed");
+    cmd.assert().success().stdout("hello\n").stderr("");
+}
@@ -0,0 +1,18 @@
+use assert_cmd::{prelude::*, Command};
+
+#[test]
+fn test_optimization_levels_behave_the_same() {
+    for level in ["-O0", "-O1"] {
+        let cmd = Command::cargo_bin("rox")
+            .expect("Cannot find cargo binary target rox")
+            .args(["--no-cache", level, "tests/data/constants.rox"])
+            .output()
+            .expect("rox binary invokation failed");
+        cmd.assert()
+            .success()
+            .stdout("86400\ntrue\n-21600\n")
+            .stderr(
+                "TypeError: Right of + binary should be a valid number when left is a number\n",
+            );
+    }
+}
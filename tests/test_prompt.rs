@@ -1,3 +1,5 @@
+use std::{fs, process};
+
 use assert_cmd::{prelude::*, Command};
 
 #[test]
@@ -21,3 +23,91 @@ fn test_prompt_interrupted_correctly() {
         .expect("rox binary invokation failed");
     cmd.assert().success().stdout("> ").stderr("");
 }
+
+#[test]
+fn test_prompt_runs_piped_lines() {
+    let cmd = Command::cargo_bin("rox")
+        .expect("Cannot invoke rox binary")
+        .env("ROX_HISTORY_FILE", "/nonexistent/history")
+        .write_stdin("var a = 1;\r\nprint a + 1;\nexit\nprint 3;\n")
+        .output()
+        .expect("rox binary invokation failed");
+    cmd.assert().success().stdout("> > 2\n> ").stderr("");
+}
+
+#[test]
+fn test_prompt_continues_incomplete_input() {
+    let cmd = Command::cargo_bin("rox")
+        .expect("Cannot invoke rox binary")
+        .write_stdin("{\nvar a = \"multi\nline\";\nprint (a\n);\n}\nexit\n")
+        .output()
+        .expect("rox binary invokation failed");
+    cmd.assert()
+        .success()
+        .stdout("> ... ... ... ... ... multi\nline\n> ")
+        .stderr("");
+}
+
+#[test]
+fn test_prompt_reports_input_left_incomplete() {
+    let cmd = Command::cargo_bin("rox")
+        .expect("Cannot invoke rox binary")
+        .write_stdin("{ print 1;\n")
+        .output()
+        .expect("rox binary invokation failed");
+    cmd.assert()
+        .success()
+        .stdout("> ... ")
+        .stderr("Parsing Error - line 0 at end: Expect '}' after block\n");
+}
+
+#[test]
+fn test_prompt_echoes_expressions() {
+    for backend in ["tree", "vm"] {
+        let cmd = Command::cargo_bin("rox")
+            .expect("Cannot invoke rox binary")
+            .arg(format!("--backend={backend}"))
+            .write_stdin("1 + 2\n\"a\" + \"b\";\nvar a = 1;\na = 2\n{ 3; }\nnil\n!a\nexit\n")
+            .output()
+            .expect("rox binary invokation failed");
+        cmd.assert()
+            .success()
+            .stdout("> 3\n> \"ab\"\n> > 2\n> > nil\n> false\n> ")
+            .stderr("");
+    }
+}
+
+#[test]
+fn test_prompt_runs_commands() {
+    let dir = std::env::temp_dir().join(format!("rox_test_prompt_commands_{}", process::id()));
+    fs::create_dir_all(&dir).expect("Cannot create the test directory");
+    let loaded = dir.join("loaded.rox");
+    let saved = dir.join("saved.rox");
+    fs::write(&loaded, "var b = a + 1;\n").expect("Cannot write the loaded file");
+
+    let input = format!(
+        "var a = 1;\nprint nope;\n:load {}\n:tokens a;\n:ast b\n:env\n:save \
+         {}\n:reset\n:env\n:bogus\n",
+        loaded.display(),
+        saved.display()
+    );
+    let cmd = Command::cargo_bin("rox")
+        .expect("Cannot invoke rox binary")
+        .write_stdin(input)
+        .output()
+        .expect("rox binary invokation failed");
+    cmd.assert()
+        .success()
+        .stdout(
+            "> > > > Identifier \"a\"\nSemicolon \";\"\nEof \"\"\n> b\n> a = 1\nb = 2\n> > > > > ",
+        )
+        .stderr(
+            "RuntimeError: Undefined variable nope\nCommand Error - Unknown command :bogus, see \
+             :help\n",
+        );
+    assert_eq!(
+        fs::read_to_string(&saved).expect("Cannot read the saved file"),
+        "var a = 1;\nvar b = a + 1;\n"
+    );
+    fs::remove_dir_all(&dir).ok();
+}
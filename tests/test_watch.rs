@@ -0,0 +1,91 @@
+use std::{
+    fs,
+    io::Read,
+    path::PathBuf,
+    process::{self, Child, Stdio},
+    sync::{Arc, Mutex},
+    thread,
+    time::{Duration, Instant},
+};
+
+use assert_cmd::cargo::CommandCargoExt;
+
+/// Wait until the output holds `count` occurrences of `text`, failing after a while
+fn wait_for(output: &Mutex<String>, text: &str, count: usize) {
+    let start = Instant::now();
+    while output.lock().unwrap().matches(text).count() < count {
+        assert!(
+            start.elapsed() < Duration::from_secs(10),
+            "{text:?} not printed {count} times in:\n{}",
+            output.lock().unwrap()
+        );
+        thread::sleep(Duration::from_millis(50));
+    }
+}
+
+/// Watching process and its directory, killed and removed when dropped, even if the test fails
+struct Watch {
+    child: Child,
+    dir: PathBuf,
+}
+
+impl Drop for Watch {
+    fn drop(&mut self) {
+        self.child.kill().ok();
+        self.child.wait().ok();
+        fs::remove_dir_all(&self.dir).ok();
+    }
+}
+
+#[test]
+fn test_watch_runs_file_again() {
+    let dir = std::env::temp_dir().join(format!("rox_test_watch_{}", process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(dir.join("lib")).expect("Cannot create temporary directory");
+    let file = dir.join("main.rox");
+    fs::write(&file, "print \"first\";\n").expect("Cannot write file");
+
+    let child = process::Command::cargo_bin("rox")
+        .expect("Cannot find cargo binary target rox")
+        .args(["--no-cache", "run", "--watch", "--watch-dir"])
+        .arg(dir.join("lib"))
+        .arg(&file)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("rox binary invokation failed");
+    let mut watch = Watch {
+        child,
+        dir: dir.clone(),
+    };
+    let output = Arc::new(Mutex::new(String::new()));
+    for mut stream in [
+        Box::new(watch.child.stdout.take().unwrap()) as Box<dyn Read + Send>,
+        Box::new(watch.child.stderr.take().unwrap()),
+    ] {
+        let output = output.clone();
+        thread::spawn(move || {
+            let mut buffer = [0; 1024];
+            while let Ok(n @ 1..) = stream.read(&mut buffer) {
+                output
+                    .lock()
+                    .unwrap()
+                    .push_str(&String::from_utf8_lossy(&buffer[..n]));
+            }
+        });
+    }
+
+    wait_for(&output, "Exited with status 0", 1);
+    fs::write(&file, "print \"second\";\n").expect("Cannot write file");
+    wait_for(&output, "Exited with status 0", 2);
+    fs::write(dir.join("lib").join("other.rox"), "").expect("Cannot write file");
+    wait_for(&output, "Exited with status 0", 3);
+    fs::write(&file, "print (;\n").expect("Cannot write file");
+    wait_for(&output, "Exited with status 65", 1);
+
+    drop(watch);
+    let output = output.lock().unwrap();
+    assert_eq!(output.matches("Running").count(), 4, "{output}");
+    assert!(output.contains("first\n"), "{output}");
+    assert_eq!(output.matches("second\n").count(), 2, "{output}");
+}